@@ -0,0 +1,110 @@
+//! Command line tool for inspecting and converting pattern files without starting the gui
+use std::path::Path;
+use std::process::ExitCode;
+
+use rusty_beads_core::chart::{self, ChartOptions};
+use rusty_beads_core::io::{self, Document, Format};
+use rusty_beads_core::model::{format_date, to_hex, BeadsLine, Color};
+use rusty_beads_core::raster::{self, RenderOptions, View};
+
+const USAGE: &str = "\
+usage:
+    beads-cli info <file>
+    beads-cli convert <input> <output> [--format beads|legacy|jbb]
+    beads-cli stringing <file>
+    beads-cli render <file> <output.png> [--rope]
+    beads-cli chart <file> <output.pdf|output.svg>";
+
+fn main() -> ExitCode {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    let result = match args.as_slice() {
+        ["info", file] => info(file),
+        ["convert", input, output] => convert(input, output, Format::default()),
+        ["convert", input, output, "--format", format] => format.parse()
+            .and_then(|format| convert(input, output, format)),
+        ["stringing", file] => stringing(file),
+        ["render", file, output] => render(file, output, View::Flat),
+        ["render", file, output, "--rope"] => render(file, output, View::Rope),
+        ["chart", file, output] => print_chart(file, output),
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{e}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn load_document(file: &str) -> Result<Document<Color>, String> {
+    io::load_file(Path::new(file)).map_err(|e| format!("{file}: {e}"))
+}
+
+fn load(file: &str) -> Result<BeadsLine<Color>, String> {
+    Ok(load_document(file)?.pattern)
+}
+
+fn info(file: &str) -> Result<(), String> {
+    let Document { metadata, pattern: line } = load_document(file)?;
+    for (name, value) in [
+        ("title", &metadata.title),
+        ("author", &metadata.author),
+        ("bead size", &metadata.bead_size),
+        ("bead type", &metadata.bead_type),
+        ("notes", &metadata.notes),
+    ] {
+        if !value.is_empty() {
+            println!("{name}: {value}");
+        }
+    }
+    for (name, date) in [("created", metadata.created), ("modified", metadata.modified)] {
+        if let Some(date) = date {
+            println!("{name}: {}", format_date(date));
+        }
+    }
+    println!("width: {}", line.width());
+    println!("height: {}", line.calculate_height());
+    println!("layout: {}", line.layout);
+    println!("schema: {}", line.schema);
+    let summary = line.summary();
+    let legend = line.legend();
+    println!("colors: {}", summary.len());
+    for (color, count) in &summary {
+        println!("    {:<3} {:<9} {count}", legend[color], to_hex(color));
+    }
+    Ok(())
+}
+
+fn convert(input: &str, output: &str, format: Format) -> Result<(), String> {
+    let document = load_document(input)?;
+    io::save(Path::new(output), &document, format).map_err(|e| format!("{output}: {e}"))
+}
+
+fn stringing(file: &str) -> Result<(), String> {
+    let line = load(file)?;
+    let legend = line.legend();
+    for (n, (bead, count)) in line.line().iter().enumerate() {
+        println!("{:>5}. {:<3} {:<9} {count}", n + 1, legend[&bead.color], to_hex(&bead.color));
+    }
+    Ok(())
+}
+
+fn render(file: &str, output: &str, view: View) -> Result<(), String> {
+    let line = load(file)?;
+    let picture = raster::render(&line, &RenderOptions { view, ..Default::default() });
+    let png = picture.to_png()?;
+    std::fs::write(output, png).map_err(|e| format!("{output}: {e}"))
+}
+
+fn print_chart(file: &str, output: &str) -> Result<(), String> {
+    let line = load(file)?;
+    let options = ChartOptions::default();
+    let data = match Path::new(output).extension().and_then(|e| e.to_str()) {
+        Some("pdf") => chart::pdf(&line, &options),
+        Some("svg") => chart::svg(&line, &options).into_bytes(),
+        _ => return Err(format!("{output}: expected .pdf or .svg")),
+    };
+    std::fs::write(output, data).map_err(|e| format!("{output}: {e}"))
+}
@@ -0,0 +1,104 @@
+//! Point edits and run lookups of a long pattern: `cargo bench -p rusty-beads-core`.
+//! The same edits are made to the plain runs the pattern was kept in before `Runs`
+use std::ops::Range;
+use std::time::Instant;
+
+use rand::Rng;
+use rusty_beads_core::model::{Bead, Runs};
+
+/// Runs in a vector rebuilt on every edit, the way `BeadsLine::set_value` worked before `Runs`
+struct Plain {
+    line: Vec<(Bead<u32>, usize)>,
+}
+
+impl Plain {
+    fn set(&mut self, index: usize, value: Bead<u32>) -> Option<Bead<u32>> {
+        let mut buf = Vec::with_capacity(self.line.len() + 2);
+        std::mem::swap(&mut buf, &mut self.line);
+        let mut result = None;
+        let mut iter = buf.into_iter();
+        let mut counter = 0;
+        while let Some((obj, count)) = iter.next() {
+            counter += count;
+            if counter <= index {
+                self.line.push((obj, count));
+                continue;
+            }
+            if value == obj {
+                self.line.push((obj, count));
+                break;
+            }
+            result = Some(obj.clone());
+            let first_part = index - (counter - count);
+            let second_part = count - first_part - 1;
+            if first_part == 0 {
+                match self.line.last_mut() {
+                    Some((prev, count)) if *prev == value => *count += 1,
+                    _ => self.line.push((value.clone(), 1)),
+                }
+            } else {
+                self.line.push((obj.clone(), first_part));
+                self.line.push((value.clone(), 1));
+            }
+            if second_part == 0 {
+                if let Some((next, count)) = iter.next() {
+                    match self.line.last_mut() {
+                        Some((prev, prev_count)) if *prev == next => *prev_count += count,
+                        _ => self.line.push((next, count)),
+                    }
+                }
+            } else {
+                self.line.push((obj, second_part));
+            }
+            break;
+        }
+        self.line.extend(iter);
+        result
+    }
+    /// Beads of the run containing the bead
+    fn run_range(&self, index: usize) -> Option<Range<usize>> {
+        let mut start = 0;
+        for (_, count) in &self.line {
+            if index < start + count {
+                return Some(start..start + count);
+            }
+            start += count;
+        }
+        None
+    }
+}
+
+fn main() {
+    let beads = 200_000;
+    let mut rng = rand::thread_rng();
+    let initial: Vec<_> = (0..beads / 10).map(|n| (Bead::from(n as u32 % 8), 10)).collect();
+    let edits: Vec<_> = (0..100_000).map(|_| (rng.gen_range(0..beads), Bead::from(rng.gen_range(0..8u32)))).collect();
+    let mut runs: Runs<u32> = initial.iter().cloned().collect();
+    let mut plain = Plain { line: initial };
+
+    let start = Instant::now();
+    for (index, value) in &edits {
+        runs.set(*index, value.clone());
+    }
+    println!("{} point edits, runs: {:?}", edits.len(), start.elapsed());
+    let start = Instant::now();
+    for (index, value) in &edits {
+        plain.set(*index, value.clone());
+    }
+    println!("{} point edits, plain: {:?}", edits.len(), start.elapsed());
+    assert!(runs.iter().eq(plain.line.iter().map(|(bead, count)| (bead, *count))), "the runs differ");
+
+    let start = Instant::now();
+    let mut found = 0;
+    for (index, _) in &edits {
+        let run = runs.run_index(*index).unwrap();
+        found += runs.run_range(run).map_or(0, |range| range.len());
+    }
+    println!("{} run lookups, runs: {:?} ({found} beads)", edits.len(), start.elapsed());
+    let start = Instant::now();
+    let mut found = 0;
+    for (index, _) in &edits {
+        found += plain.run_range(*index).map_or(0, |range| range.len());
+    }
+    println!("{} run lookups, plain: {:?} ({found} beads)", edits.len(), start.elapsed());
+}
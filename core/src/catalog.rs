@@ -0,0 +1,209 @@
+//! Seed beads of a manufacturer chart with their order codes, and the perceptual matching of colors to them.
+//! A catalog file has a bead per line: the code, the sRGB as `#RRGGBB` and the name separated by tabs,
+//! the lines starting with `#` are comments. The finishes like luster or silver lining are not seen
+use std::collections::HashMap;
+
+use crate::model::{from_hex, BeadsLine, Color, ColorTrait, Rgba};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bead {
+    pub code: String,
+    pub name: String,
+    pub rgb: [u8; 3],
+}
+
+impl Bead {
+    pub fn color<T: Rgba>(&self) -> T {
+        let [r, g, b] = self.rgb;
+        T::from_rgba([r, g, b, 255])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Catalog {
+    pub name: String,
+    pub beads: Vec<Bead>,
+}
+
+fn fields(line: &str) -> Vec<&str> {
+    line.split('\t').map(str::trim).collect()
+}
+
+fn data_lines(text: &str) -> impl Iterator<Item=(usize, &str)> {
+    text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'))
+}
+
+/// Whether the text is a catalog file rather than a palette
+pub fn is_catalog(text: &str) -> bool {
+    data_lines(text).next().is_some_and(|(_, line)| fields(line).len() == 3)
+}
+
+/// Number of the code and the letters after it, "TR-11-46D" is (46, "D")
+fn code_order(code: &str) -> (u32, &str) {
+    let last = code.rsplit('-').next().unwrap_or(code);
+    let digits = last.find(|c: char| !c.is_ascii_digit()).unwrap_or(last.len());
+    (last[..digits].parse().unwrap_or(0), &last[digits..])
+}
+
+/// Reads the beads of a catalog file under the name, sorted by code
+pub fn load(text: &str, name: &str) -> Result<Catalog, String> {
+    let mut beads = Vec::new();
+    for (n, line) in data_lines(text) {
+        let wrong = |e: String| format!("line {}: {e}", n + 1);
+        let &[code, hex, name] = fields(line).as_slice() else {
+            return Err(wrong(format!("expected code, color and name: {line}")));
+        };
+        let Color { r, g, b } = from_hex(hex).map_err(|e| wrong(format!("wrong color {hex}, {e}")))?;
+        beads.push(Bead { code: code.to_string(), name: name.to_string(), rgb: [r, g, b] });
+    }
+    if beads.is_empty() {
+        return Err("no beads".to_string());
+    }
+    beads.sort_by(|a, b| code_order(&a.code).cmp(&code_order(&b.code)).then_with(|| a.code.cmp(&b.code)));
+    if let Some(pair) = beads.windows(2).find(|pair| pair[0].code == pair[1].code) {
+        return Err(format!("{} is given twice", pair[0].code));
+    }
+    Ok(Catalog { name: name.to_string(), beads })
+}
+
+impl Catalog {
+    pub fn colors<T: Rgba>(&self) -> Vec<T> {
+        self.beads.iter().map(Bead::color).collect()
+    }
+    /// Bead of exactly the color
+    pub fn find<T: Rgba>(&self, color: &T) -> Option<&Bead> {
+        let [r, g, b, _] = color.to_rgba();
+        self.beads.iter().find(|bead| bead.rgb == [r, g, b])
+    }
+    /// Bead looking the closest to the color by CIEDE2000, the transparency is not taken into account
+    pub fn nearest<T: Rgba>(&self, color: &T) -> &Bead {
+        let [r, g, b, _] = color.to_rgba();
+        let target = lab([r, g, b]);
+        self.beads.iter()
+            .map(|bead| (bead, ciede2000(target, lab(bead.rgb))))
+            .fold((&self.beads[0], f64::MAX), |best, current| if current.1 < best.1 { current } else { best })
+            .0
+    }
+    /// Pattern with every color replaced by its nearest bead, the transparent empty beads are left as they are
+    pub fn map_line<T: ColorTrait + Rgba>(&self, line: &BeadsLine<T>) -> BeadsLine<T> {
+        let matched: HashMap<T, T> = line.summary().keys()
+            .map(|color| (color.clone(), self.matched(color)))
+            .collect();
+        line.map(|color| matched.get(color).cloned().unwrap_or_else(|| self.matched(color)))
+    }
+    fn matched<T: Rgba>(&self, color: &T) -> T {
+        match color.to_rgba() {
+            [_, _, _, 0] => T::from_rgba(color.to_rgba()),
+            _ => self.nearest(color).color(),
+        }
+    }
+}
+
+/// CIE L*a*b* of an sRGB color under the D65 white point
+fn lab([r, g, b]: [u8; 3]) -> [f64; 3] {
+    let linear = |c: u8| {
+        let c = f64::from(c) / 255.0;
+        if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+    };
+    let (r, g, b) = (linear(r), linear(g), linear(b));
+    let x = (0.4124564 * r + 0.3575761 * g + 0.1804375 * b) / 0.95047;
+    let y = 0.2126729 * r + 0.7151522 * g + 0.0721750 * b;
+    let z = (0.0193339 * r + 0.1191920 * g + 0.9503041 * b) / 1.08883;
+    let f = |t: f64| if t > 216.0 / 24389.0 { t.cbrt() } else { (24389.0 / 27.0 * t + 16.0) / 116.0 };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+/// Perceptual difference of two L*a*b* colors, about 1 is the smallest one noticed
+pub fn ciede2000([l1, a1, b1]: [f64; 3], [l2, a2, b2]: [f64; 3]) -> f64 {
+    let pow25_7 = 25f64.powi(7);
+    let c7 = ((a1.hypot(b1) + a2.hypot(b2)) / 2.0).powi(7);
+    let g = 0.5 * (1.0 - (c7 / (c7 + pow25_7)).sqrt());
+    let (a1, a2) = (a1 * (1.0 + g), a2 * (1.0 + g));
+    let (c1, c2) = (a1.hypot(b1), a2.hypot(b2));
+    let hue = |a: f64, b: f64| if a == 0.0 && b == 0.0 { 0.0 } else { b.atan2(a).to_degrees().rem_euclid(360.0) };
+    let (h1, h2) = (hue(a1, b1), hue(a2, b2));
+    let chromatic = c1 * c2 != 0.0;
+    let dh = match h2 - h1 {
+        _ if !chromatic => 0.0,
+        d if d > 180.0 => d - 360.0,
+        d if d < -180.0 => d + 360.0,
+        d => d,
+    };
+    let (dl, dc, dh) = (l2 - l1, c2 - c1, 2.0 * (c1 * c2).sqrt() * (dh.to_radians() / 2.0).sin());
+    let (l, c) = ((l1 + l2) / 2.0, (c1 + c2) / 2.0);
+    let h = match h1 + h2 {
+        sum if !chromatic => sum,
+        sum if (h1 - h2).abs() <= 180.0 => sum / 2.0,
+        sum if sum < 360.0 => (sum + 360.0) / 2.0,
+        sum => (sum - 360.0) / 2.0,
+    };
+    let cos = |degrees: f64| degrees.to_radians().cos();
+    let t = 1.0 - 0.17 * cos(h - 30.0) + 0.24 * cos(2.0 * h) + 0.32 * cos(3.0 * h + 6.0) - 0.20 * cos(4.0 * h - 63.0);
+    let rotation = 30.0 * (-((h - 275.0) / 25.0).powi(2)).exp();
+    let rc = 2.0 * (c.powi(7) / (c.powi(7) + pow25_7)).sqrt();
+    let l50 = (l - 50.0).powi(2);
+    let sl = 1.0 + 0.015 * l50 / (20.0 + l50).sqrt();
+    let sc = 1.0 + 0.045 * c;
+    let sh = 1.0 + 0.015 * c * t;
+    let rt = -(2.0 * rotation).to_radians().sin() * rc;
+    let (dl, dc, dh) = (dl / sl, dc / sc, dh / sh);
+    (dl * dl + dc * dc + dh * dh + rt * dc * dh).sqrt()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn ciede2000_matches_reference() {
+        // pairs of the test data of Sharma, Wu and Dalal
+        let pairs = [
+            ([50.0, 2.6772, -79.7751], [50.0, 0.0, -82.7485], 2.0425),
+            ([50.0, 0.0, 0.0], [50.0, -1.0, 2.0], 2.3669),
+            ([50.0, 2.5, 0.0], [73.0, 25.0, -18.0], 27.1492),
+            ([60.2574, -34.0099, 36.2677], [60.4626, -34.1751, 39.4387], 1.2644),
+            ([22.7233, 20.0904, -46.6940], [23.0331, 14.9730, -42.5619], 2.0373),
+        ];
+        for (a, b, expected) in pairs {
+            assert!((ciede2000(a, b) - expected).abs() < 1e-4, "{:?} {:?}", a, b);
+            assert!((ciede2000(b, a) - expected).abs() < 1e-4, "{:?} {:?}", b, a);
+        }
+        let white = lab([255, 255, 255]);
+        assert!((white[0] - 100.0).abs() < 1e-3 && white[1].abs() < 1e-3 && white[2].abs() < 1e-3, "{:?}", white);
+    }
+
+    const CHART: &str = "# test chart\n3\t#C00000\tRed\n1\t#000000\tBlack\n\n2\t#FFFFFF\tWhite\n";
+
+    #[test]
+    fn catalog_files_are_checked() {
+        assert!(is_catalog(CHART));
+        assert!(!is_catalog("#FF0000 red\n"));
+        assert!(!is_catalog("GIMP Palette\n"));
+        let catalog = load(CHART, "test").unwrap();
+        assert_eq!(vec!["1", "2", "3"], catalog.beads.iter().map(|bead| bead.code.as_str()).collect::<Vec<_>>());
+        let codes = load("TR-11-55\t#3BB0B8\tTurquoise\nTR-11-43D\t#F5D72A\tLemon\nTR-11-43\t#4E7FC4\tCornflower", "").unwrap();
+        assert_eq!(vec!["TR-11-43", "TR-11-43D", "TR-11-55"], codes.beads.iter().map(|bead| bead.code.as_str()).collect::<Vec<_>>());
+        assert_eq!("1 is given twice", load("1\t#000000\tBlack\n1\t#FFFFFF\tWhite", "").unwrap_err());
+        assert_eq!("line 1: wrong color #00000, expected #RRGGBB or #RRGGBBAA", load("1\t#00000\tBlack", "").unwrap_err());
+        assert_eq!("line 2: expected code, color and name: 2 #FFFFFF White", load("1\t#000000\tBlack\n2 #FFFFFF White", "").unwrap_err());
+        assert_eq!("no beads", load("# empty\n", "").unwrap_err());
+    }
+
+    #[test]
+    fn colors_are_matched() {
+        let catalog = load(CHART, "test").unwrap();
+        for bead in &catalog.beads {
+            assert_eq!(bead, catalog.nearest(&bead.color::<Color>()));
+        }
+        assert_eq!("1", catalog.nearest(&Color { r: 30, g: 20, b: 20 }).code);
+        assert_eq!("3", catalog.nearest(&Color { r: 180, g: 20, b: 30 }).code);
+        assert_eq!(None, catalog.find(&Color { r: 180, g: 20, b: 30 }));
+        let mut line = BeadsLine::<Color>::default();
+        line.set_value(Color { r: 10, g: 10, b: 10 }, crate::model::Coord { x: 0, y: 0 });
+        let mapped = catalog.map_line(&line);
+        let codes: Vec<_> = mapped.summary().keys().map(|color| catalog.find(color).unwrap().code.as_str()).collect();
+        assert_eq!(vec!["1", "2"], codes);
+        assert_eq!(line.summary().values().collect::<Vec<_>>(), mapped.summary().values().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,421 @@
+//! Printable stringing chart: color legend, pattern preview and numbered runs with checkboxes.
+//! The chart is laid out once into pages and written either as a multi-page pdf or as svg.
+use std::fmt::Write;
+
+use indexmap::IndexMap;
+
+use crate::model::{BeadsLine, ColorTrait, Rgba};
+
+const LINE: f32 = 16.0;
+const LEGEND_CELL: f32 = 80.0;
+const RUN_CELL: f32 = 130.0;
+const PREVIEW_HEIGHT: f32 = 260.0;
+const PREVIEW_BEAD: f32 = 8.0;
+
+/// Page geometry in points
+#[derive(Debug, Clone)]
+pub struct ChartOptions {
+    pub page: [f32; 2],
+    pub margin: f32,
+    pub rotation: isize,
+}
+
+impl Default for ChartOptions {
+    /// A4 portrait
+    fn default() -> Self {
+        Self { page: [595.0, 842.0], margin: 36.0, rotation: 0 }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Anchor {
+    Start,
+    Middle,
+    End,
+}
+
+#[derive(Debug, Clone)]
+enum Item {
+    Rect { x: f32, y: f32, width: f32, height: f32, fill: Option<[u8; 3]>, stroke: f32 },
+    Line { from: [f32; 2], to: [f32; 2], width: f32 },
+    Text { x: f32, y: f32, size: f32, anchor: Anchor, color: [u8; 3], text: String },
+}
+
+/// Items of one page, y grows downwards
+#[derive(Debug, Default)]
+struct Page {
+    items: Vec<Item>,
+}
+
+impl Page {
+    fn rect(&mut self, x: f32, y: f32, width: f32, height: f32, fill: Option<[u8; 3]>, stroke: f32) {
+        self.items.push(Item::Rect { x, y, width, height, fill, stroke });
+    }
+    fn text(&mut self, x: f32, y: f32, size: f32, anchor: Anchor, color: [u8; 3], text: String) {
+        self.items.push(Item::Text { x, y, size, anchor, color, text });
+    }
+    /// Color swatch with the symbol written over it in a contrasting color
+    fn swatch(&mut self, x: f32, y: f32, width: f32, color: [u8; 3], symbol: &str) {
+        self.rect(x, y, width, 12.0, Some(color), 0.5);
+        self.text(x + width / 2.0, y + 9.5, 9.0, Anchor::Middle, contrast(color), symbol.to_string());
+    }
+    fn checkbox(&mut self, x: f32, y: f32, checked: bool) {
+        self.rect(x, y, 9.0, 9.0, None, 0.7);
+        if checked {
+            self.items.push(Item::Line { from: [x, y], to: [x + 9.0, y + 9.0], width: 0.7 });
+            self.items.push(Item::Line { from: [x + 9.0, y], to: [x, y + 9.0], width: 0.7 });
+        }
+    }
+}
+
+fn rgb(rgba: [u8; 4]) -> [u8; 3] {
+    [rgba[0], rgba[1], rgba[2]]
+}
+
+fn contrast([r, g, b]: [u8; 3]) -> [u8; 3] {
+    let luminance = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+    if luminance > 140.0 { [0, 0, 0] } else { [255, 255, 255] }
+}
+
+fn layout<T: ColorTrait + Rgba>(line: &BeadsLine<T>, legend: &IndexMap<T, String>, options: &ChartOptions) -> Vec<Page> {
+    let [page_width, page_height] = options.page;
+    let margin = options.margin;
+    let content_width = page_width - 2.0 * margin;
+    let bottom = page_height - margin - LINE;
+    let width = line.width();
+    let height = line.calculate_height();
+    let summary = line.summary();
+    let black = [0, 0, 0];
+
+    let mut pages = vec![Page::default()];
+    let mut page = pages.last_mut().unwrap();
+    let total: usize = summary.values().sum();
+    let geometry = if line.layout.is_flat() { line.layout.to_string() } else { line.schema.to_string() };
+    let header = format!("{width} x {height}  {geometry}  {} runs  {total} beads", line.line().len());
+    page.text(margin, margin + 12.0, 14.0, Anchor::Start, black, header);
+    let mut y = margin + 28.0;
+
+    let [extent_x, extent_y] = line.extent();
+    let bead = (content_width / extent_x)
+        .min(PREVIEW_HEIGHT / extent_y)
+        .min(PREVIEW_BEAD);
+    for row in line.table(options.rotation, 0) {
+        for (pos, (_, item)) in row.iter.enumerate() {
+            let [x, top] = line.place(row.row, pos);
+            page.rect(margin + x * bead, y + top * bead, bead, bead, Some(rgb(item.color.to_rgba())), 0.0);
+        }
+    }
+    y += extent_y * bead + LINE;
+
+    let columns = ((content_width / LEGEND_CELL) as usize).max(1);
+    for (n, (color, count)) in summary.iter().enumerate() {
+        if n > 0 && n % columns == 0 {
+            y += LINE;
+        }
+        if n % columns == 0 && y > bottom {
+            pages.push(Page::default());
+            page = pages.last_mut().unwrap();
+            y = margin;
+        }
+        let x = margin + (n % columns) as f32 * LEGEND_CELL;
+        page.swatch(x, y, 22.0, rgb(color.to_rgba()), &legend[color]);
+        page.text(x + 26.0, y + 9.5, 9.0, Anchor::Start, black, count.to_string());
+    }
+    y += 2.0 * LINE;
+    if y > bottom {
+        pages.push(Page::default());
+        page = pages.last_mut().unwrap();
+        y = margin;
+    }
+
+    let columns = ((content_width / RUN_CELL) as usize).max(1);
+    let (mut column, mut top) = (0, y);
+    let mut strung = 0;
+    for (n, (item, count)) in line.line().iter().enumerate() {
+        strung += count;
+        if y > bottom {
+            column += 1;
+            y = top;
+            if column == columns {
+                pages.push(Page::default());
+                page = pages.last_mut().unwrap();
+                column = 0;
+                top = margin;
+                y = top;
+            }
+        }
+        let x = margin + column as f32 * RUN_CELL;
+        page.checkbox(x, y + 1.5, strung <= line.progress());
+        page.text(x + 44.0, y + 9.5, 9.0, Anchor::End, black, format!("{}.", n + 1));
+        page.swatch(x + 50.0, y, 22.0, rgb(item.color.to_rgba()), &legend[&item.color]);
+        page.text(x + 78.0, y + 9.5, 9.0, Anchor::Start, black, count.to_string());
+        y += LINE;
+    }
+
+    let count = pages.len();
+    for (n, page) in pages.iter_mut().enumerate() {
+        let text = format!("{} / {count}", n + 1);
+        page.text(page_width / 2.0, page_height - margin / 2.0, 8.0, Anchor::Middle, black, text);
+    }
+    pages
+}
+
+fn escape_xml(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+/// All pages one under another in a single svg document
+pub fn svg<T: ColorTrait + Rgba>(line: &BeadsLine<T>, options: &ChartOptions) -> String {
+    let pages = layout(line, &line.legend(), options);
+    let [width, height] = options.page;
+    let total = height * pages.len() as f32;
+    let mut out = String::new();
+    let _ = writeln!(out, r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}pt" height="{total}pt" viewBox="0 0 {width} {total}" font-family="Helvetica, Arial, sans-serif">"#);
+    for (n, page) in pages.iter().enumerate() {
+        let _ = writeln!(out, r#"<g transform="translate(0 {})">"#, n as f32 * height);
+        let _ = writeln!(out, r#"<rect width="{width}" height="{height}" fill="white" stroke="gray" stroke-width="0.5"/>"#);
+        for item in &page.items {
+            let _ = match item {
+                Item::Rect { x, y, width, height, fill, stroke } => {
+                    let fill = fill.map_or("none".to_string(), |[r, g, b]| format!("rgb({r},{g},{b})"));
+                    let stroke = if *stroke > 0.0 { format!(r#" stroke="black" stroke-width="{stroke}""#) } else { String::new() };
+                    writeln!(out, r#"<rect x="{x:.2}" y="{y:.2}" width="{width:.2}" height="{height:.2}" fill="{fill}"{stroke}/>"#)
+                },
+                Item::Line { from: [x1, y1], to: [x2, y2], width } => {
+                    writeln!(out, r#"<line x1="{x1:.2}" y1="{y1:.2}" x2="{x2:.2}" y2="{y2:.2}" stroke="black" stroke-width="{width}"/>"#)
+                },
+                Item::Text { x, y, size, anchor, color: [r, g, b], text } => {
+                    let anchor = match anchor {
+                        Anchor::Start => "start",
+                        Anchor::Middle => "middle",
+                        Anchor::End => "end",
+                    };
+                    writeln!(out, r#"<text x="{x:.2}" y="{y:.2}" font-size="{size}" text-anchor="{anchor}" fill="rgb({r},{g},{b})">{}</text>"#, escape_xml(text))
+                },
+            };
+        }
+        out.push_str("</g>\n");
+    }
+    out.push_str("</svg>\n");
+    out
+}
+
+/// Advance of Helvetica glyphs in thousandths of the font size
+fn glyph_width(c: char) -> u32 {
+    match c {
+        ' ' | '.' | ',' | ':' | '/' | 'I' | 'f' | 't' => 278,
+        'i' | 'j' | 'l' => 222,
+        '-' | 'r' => 333,
+        '{' | '}' => 334,
+        'J' | 'c' | 'k' | 's' | 'v' | 'x' | 'y' | 'z' => 500,
+        'F' | 'T' | 'Z' => 611,
+        'A' | 'B' | 'E' | 'K' | 'P' | 'S' | 'V' | 'X' | 'Y' => 667,
+        'C' | 'D' | 'H' | 'N' | 'R' | 'U' | 'w' => 722,
+        'G' | 'O' | 'Q' => 778,
+        'M' | 'm' => 833,
+        'W' => 944,
+        _ => 556,
+    }
+}
+
+/// Character escaped for a literal string in WinAnsiEncoding, none when the encoding has no such character
+fn win_ansi(c: char) -> Option<String> {
+    match c {
+        '(' | ')' | '\\' => Some(format!("\\{c}")),
+        ' '..='~' => Some(c.to_string()),
+        '\u{a0}'..='\u{ff}' => Some(format!("\\{:03o}", c as u32)),
+        '€' => Some("\\200".to_string()),
+        '†' => Some("\\206".to_string()),
+        '‡' => Some("\\207".to_string()),
+        '•' => Some("\\225".to_string()),
+        _ => None,
+    }
+}
+
+/// Literal string in WinAnsiEncoding, characters out of it are replaced with `?`
+fn escape_pdf(text: &str) -> String {
+    text.chars().map(|c| win_ansi(c).unwrap_or_else(|| "?".to_string())).collect()
+}
+
+/// Legend printable with the standard font: the colors with assigned symbols out of WinAnsiEncoding
+/// get generated symbols instead, so that no two colors print the same
+fn pdf_legend<T: ColorTrait>(line: &BeadsLine<T>) -> IndexMap<T, String> {
+    let mut symbols = line.symbols.clone();
+    for (color, symbol) in line.symbols.iter() {
+        if !symbol.chars().all(|c| win_ansi(c).is_some()) {
+            symbols.assign(color.clone(), "");
+        }
+    }
+    symbols.legend(line.summary().keys())
+}
+
+fn page_stream(page: &Page, height: f32) -> String {
+    let unit = |c: u8| c as f32 / 255.0;
+    let mut out = String::new();
+    for item in &page.items {
+        let _ = match item {
+            Item::Rect { x, y, width, height: h, fill, stroke } => {
+                let op = match (fill, *stroke > 0.0) {
+                    (Some(_), true) => "B",
+                    (Some(_), false) => "f",
+                    (None, true) => "S",
+                    (None, false) => continue,
+                };
+                if let Some([r, g, b]) = fill {
+                    let _ = write!(out, "{:.3} {:.3} {:.3} rg ", unit(*r), unit(*g), unit(*b));
+                }
+                writeln!(out, "{stroke} w 0 G {x:.2} {:.2} {width:.2} {h:.2} re {op}", height - y - h)
+            },
+            Item::Line { from: [x1, y1], to: [x2, y2], width } => {
+                writeln!(out, "{width} w 0 G {x1:.2} {:.2} m {x2:.2} {:.2} l S", height - y1, height - y2)
+            },
+            Item::Text { x, y, size, anchor, color: [r, g, b], text } => {
+                let advance = text.chars().map(glyph_width).sum::<u32>() as f32 * size / 1000.0;
+                let x = match anchor {
+                    Anchor::Start => *x,
+                    Anchor::Middle => x - advance / 2.0,
+                    Anchor::End => x - advance,
+                };
+                writeln!(out, "{:.3} {:.3} {:.3} rg BT /F1 {size} Tf {x:.2} {:.2} Td ({}) Tj ET",
+                    unit(*r), unit(*g), unit(*b), height - y, escape_pdf(text))
+            },
+        };
+    }
+    out
+}
+
+/// Multi-page pdf using the standard Helvetica font
+pub fn pdf<T: ColorTrait + Rgba>(line: &BeadsLine<T>, options: &ChartOptions) -> Vec<u8> {
+    let pages = layout(line, &pdf_legend(line), options);
+    let [width, height] = options.page;
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_string(),
+        String::new(),
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_string(),
+    ];
+    let mut kids = Vec::new();
+    for page in &pages {
+        let id = objects.len() + 1;
+        kids.push(format!("{id} 0 R"));
+        objects.push(format!("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {width} {height}] \
+            /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>", id + 1));
+        let stream = page_stream(page, height);
+        objects.push(format!("<< /Length {} >>\nstream\n{stream}endstream", stream.len()));
+    }
+    objects[1] = format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), pages.len());
+
+    let mut out = String::from("%PDF-1.4\n");
+    let mut offsets = Vec::new();
+    for (n, object) in objects.iter().enumerate() {
+        offsets.push(out.len());
+        let _ = writeln!(out, "{} 0 obj\n{object}\nendobj", n + 1);
+    }
+    let xref = out.len();
+    let _ = write!(out, "xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1);
+    for offset in offsets {
+        let _ = writeln!(out, "{offset:010} 00000 n ");
+    }
+    let _ = write!(out, "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref}\n%%EOF\n", objects.len() + 1);
+    out.into_bytes()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::{Color, Coord};
+    use crate::model::generated_symbol;
+
+    fn striped(runs: usize) -> BeadsLine<Color> {
+        let mut line = BeadsLine::default();
+        let colors = [Color {r: 255, g: 0, b: 0}, Color {r: 0, g: 0, b: 255}];
+        for n in 0..runs {
+            line.set_value(colors[n % 2], Coord {x: n % line.width(), y: n / line.width()});
+        }
+        line
+    }
+
+    #[test]
+    fn runs_flow_into_pages() {
+        let line = striped(10);
+        assert_eq!(1, layout(&line, &line.legend(), &ChartOptions::default()).len());
+        let line = striped(1000);
+        let runs = line.line().len();
+        let pages = layout(&line, &line.legend(), &ChartOptions::default());
+        assert!(pages.len() > 1);
+        let checkboxes = pages.iter().flat_map(|page| &page.items)
+            .filter(|item| matches!(item, Item::Rect { fill: None, .. })).count();
+        assert_eq!(runs, checkboxes);
+        for page in &pages {
+            for item in &page.items {
+                if let Item::Rect { y, height, .. } = item {
+                    assert!(y + height < 842.0 - 36.0);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn long_legend_flows_into_pages() {
+        let mut line = BeadsLine::default();
+        for n in 0..1000 {
+            let color = Color {r: (n % 256) as u8, g: (n / 256) as u8, b: 7};
+            line.set_value(color, Coord {x: n % line.width(), y: n / line.width()});
+        }
+        let options = ChartOptions::default();
+        let pages = layout(&line, &line.legend(), &options);
+        let swatches = pages.iter().flat_map(|page| &page.items)
+            .filter(|item| matches!(item, Item::Rect { fill: Some(_), stroke, .. } if *stroke > 0.0)).count();
+        assert_eq!(line.summary().len() + line.line().len(), swatches);
+        for page in &pages {
+            for item in &page.items {
+                if let Item::Rect { y, height, .. } = item {
+                    assert!(*y >= options.margin && y + height < 842.0 - 36.0, "{}", y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn legend_uses_summary_order() {
+        let mut line = striped(4);
+        line.set_progress(line.run_range(0).unwrap().end);
+        let colors = line.summary().len();
+        let svg = svg(&line, &ChartOptions::default());
+        for n in 0..colors {
+            assert!(svg.contains(&format!(">{}</text>", generated_symbol(n))));
+        }
+        assert!(!svg.contains(&format!(">{}</text>", generated_symbol(colors))));
+        assert_eq!(2, svg.matches("<line ").count());
+    }
+
+    #[test]
+    fn pdf_prints_distinct_symbols() {
+        let mut line = striped(4);
+        let colors: Vec<_> = line.summary().keys().cloned().collect();
+        line.symbols.assign(colors[0], "★");
+        line.symbols.assign(colors[1], "♥");
+        let legend = pdf_legend(&line);
+        assert_eq!(vec!["A", "B", "C"], legend.values().map(String::as_str).collect::<Vec<_>>());
+        line.symbols.assign(colors[1], "A§");
+        let legend = pdf_legend(&line);
+        assert_eq!(vec!["A", "A§", "B"], legend.values().map(String::as_str).collect::<Vec<_>>());
+        let pdf = String::from_utf8(pdf(&line, &ChartOptions::default())).unwrap();
+        assert!(!pdf.contains("(?) Tj"));
+        assert!(pdf.contains("(A\\247) Tj"));
+        assert!(svg(&line, &ChartOptions::default()).contains(">★</text>"));
+    }
+
+    #[test]
+    fn pdf_xref_points_to_objects() {
+        let line = striped(1000);
+        let pdf = String::from_utf8(pdf(&line, &ChartOptions::default())).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4"));
+        let start: usize = pdf.lines().rev().nth(1).unwrap().parse().unwrap();
+        assert!(pdf[start..].starts_with("xref"));
+        let entries = pdf[start..].lines().skip(3);
+        for (n, entry) in entries.take_while(|l| l.ends_with(" n ")).enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", n + 1)));
+        }
+    }
+}
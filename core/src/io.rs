@@ -0,0 +1,399 @@
+use std::fs::File;
+use std::num::NonZeroUsize;
+use std::io::{Write, Read};
+use std::path::Path;
+use std::str::FromStr;
+
+use crate::model::{from_hex, to_hex, Color, ColorTrait, Layout, Metadata, Rgba, Schema};
+use crate::model::beads::BeadsLine;
+use crate::model::grid::SimplifiedGrid;
+use crate::jbead;
+use serde::{Serialize, Deserialize};
+use serde::de::DeserializeOwned;
+use indexmap::IndexSet;
+use serde_json::{json, Value};
+
+/// Formats a pattern can be written in
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Format {
+    /// actual format, colors are stored as is
+    #[default]
+    Beads,
+    /// format of the first versions, colors are stored as rgb
+    Legacy,
+    /// pattern of JBead
+    JBead,
+}
+
+impl Format {
+    /// Format written to the file with the extension
+    pub fn of_path(path: &Path) -> Self {
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("jbb") => Self::JBead,
+            _ => Self::Beads,
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "beads" => Ok(Self::Beads),
+            "legacy" => Ok(Self::Legacy),
+            "jbb" => Ok(Self::JBead),
+            other => Err(format!("unknown format: {other}")),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct LegacyRun {
+    color: Color,
+    filled: bool,
+}
+
+/// Pattern as written by the first versions: rgb colors, progress as flags of the runs
+#[derive(Serialize)]
+struct LegacyLine {
+    width: usize,
+    line: Vec<(LegacyRun, usize)>,
+    schema: Schema,
+}
+
+impl<T: ColorTrait> From<&BeadsLine<T>> for LegacyLine where Color: From<T> {
+    fn from(value: &BeadsLine<T>) -> Self {
+        let mut strung = 0;
+        let line = value.line().iter().map(|(bead, count)| {
+            strung += count;
+            let run = LegacyRun { color: bead.color.clone().into(), filled: strung <= value.progress() };
+            (run, count)
+        }).collect();
+        Self { width: value.width, line, schema: value.schema }
+    }
+}
+
+/// Version of the pattern files written by this build
+pub const VERSION: u64 = 2;
+
+/// Pattern file: the beads, their legend symbols included, and the description
+#[derive(Debug, Clone, Default)]
+pub struct Document<T: ColorTrait> {
+    pub metadata: Metadata,
+    pub pattern: BeadsLine<T>,
+}
+
+impl<T: ColorTrait> From<BeadsLine<T>> for Document<T> {
+    fn from(pattern: BeadsLine<T>) -> Self {
+        Self { metadata: Default::default(), pattern }
+    }
+}
+
+/// Pattern as written since the version 2: every color once as `#RRGGBB` or `#RRGGBBAA`,
+/// the runs and the legend symbols refer to the colors by their index in the table
+#[derive(Serialize, Deserialize)]
+struct Pattern {
+    width: usize,
+    schema: Schema,
+    layout: Layout,
+    colors: Vec<String>,
+    line: Vec<(usize, usize)>,
+    symbols: Vec<(usize, String)>,
+    progress: usize,
+}
+
+impl<T: ColorTrait + Rgba> From<&BeadsLine<T>> for Pattern {
+    fn from(value: &BeadsLine<T>) -> Self {
+        let runs = value.line().iter().map(|(bead, _)| &bead.color);
+        let colors: IndexSet<&T> = runs.chain(value.symbols.iter().map(|(color, _)| color)).collect();
+        let id = |color: &T| colors.get_index_of(color).unwrap();
+        Self {
+            width: value.width,
+            schema: value.schema,
+            layout: value.layout,
+            colors: colors.iter().map(|color| to_hex(*color)).collect(),
+            line: value.line().iter().map(|(bead, count)| (id(&bead.color), count)).collect(),
+            symbols: value.symbols.iter().map(|(color, symbol)| (id(color), symbol.to_string())).collect(),
+            progress: value.progress(),
+        }
+    }
+}
+
+impl Pattern {
+    fn line<T: ColorTrait + Rgba>(self) -> Result<BeadsLine<T>, String> {
+        if self.width == 0 {
+            return Err("zero width".to_string());
+        }
+        if let Some(run) = self.line.iter().position(|&(_, count)| count == 0) {
+            return Err(format!("empty run {}", run + 1));
+        }
+        let total: usize = self.line.iter().map(|&(_, count)| count).sum();
+        if !total.is_multiple_of(self.width) {
+            return Err(format!("{total} beads are not whole rows of {}", self.width));
+        }
+        let colors = self.colors.iter()
+            .map(|hex| from_hex(hex).map_err(|e| format!("wrong color {hex}, {e}")))
+            .collect::<Result<Vec<T>, _>>()?;
+        let color = |id: usize| colors.get(id).cloned().ok_or_else(|| format!("unknown color {id}"));
+        let runs = self.line.iter().map(|&(id, count)| Ok((color(id)?, count))).collect::<Result<Vec<_>, String>>()?;
+        let mut line = BeadsLine::from_runs(self.width, runs, self.schema, self.layout);
+        for (id, symbol) in &self.symbols {
+            line.symbols.assign(color(*id)?, symbol);
+        }
+        line.set_progress(self.progress);
+        Ok(line)
+    }
+}
+
+/// Written form of the document, the version goes first
+#[derive(Serialize)]
+struct Envelope<'a> {
+    version: u64,
+    metadata: &'a Metadata,
+    pattern: Pattern,
+}
+
+/// Document as read after the migrations
+#[derive(Deserialize)]
+struct Stored {
+    #[serde(default)]
+    metadata: Metadata,
+    pattern: Pattern,
+}
+
+/// Steps bringing the file to the next version, the n-th one upgrades the version n
+fn migrations<T: ColorTrait + Rgba + Serialize + DeserializeOwned + From<Color>>() -> [fn(Value) -> Result<Value, String>; VERSION as usize] {
+    [unversioned::<T>, color_table::<T>]
+}
+
+/// Files without a version are the bare pattern. The first versions stored the colors as rgb
+/// and the progress as the `filled` flag of the runs, the schemas had names (see `SchemaCompat`),
+/// the layout and the symbols came later
+fn unversioned<T: ColorTrait + Serialize + DeserializeOwned + From<Color>>(pattern: Value) -> Result<Value, String> {
+    let rgb = pattern.pointer("/line/0/0/color").is_some_and(Value::is_object);
+    let pattern = if rgb {
+        let line: BeadsLine<Color> = serde_json::from_value(pattern).map_err(|e|e.to_string())?;
+        serde_json::to_value(line.map(|c|T::from(*c)))
+    } else {
+        let line: BeadsLine<T> = serde_json::from_value(pattern).map_err(|e|e.to_string())?;
+        serde_json::to_value(line)
+    }.map_err(|e|e.to_string())?;
+    Ok(json!({ "version": 1, "metadata": {}, "pattern": pattern }))
+}
+
+/// The version 1 kept the pattern as it is in memory, with the colors of every run as raw bytes
+fn color_table<T: ColorTrait + Rgba + DeserializeOwned>(mut document: Value) -> Result<Value, String> {
+    let pattern = document.get_mut("pattern").map(Value::take).ok_or("missing field `pattern`")?;
+    let line: BeadsLine<T> = serde_json::from_value(pattern).map_err(|e|e.to_string())?;
+    document["pattern"] = serde_json::to_value(Pattern::from(&line)).map_err(|e|e.to_string())?;
+    document["version"] = json!(2);
+    Ok(document)
+}
+
+/// Reads the file of any version migrating it step by step to the actual one, or a pattern of JBead
+pub fn load_document<T: ColorTrait + Rgba + Serialize + DeserializeOwned + From<Color>>(s: &str) -> Result<Document<T>, String> {
+    if jbead::is_jbead(s) {
+        return jbead::load(s).map_err(|e|format!("jbead: {e}"));
+    }
+    let mut value: Value = serde_json::from_str(s).map_err(|e|format!("not a pattern file: {e}"))?;
+    let version = match value.get("version") {
+        Some(version) => version.as_u64().ok_or_else(|| format!("wrong version: {version}"))?,
+        None => 0,
+    };
+    if version > VERSION {
+        return Err(format!("version {version} is newer than the supported {VERSION}"));
+    }
+    for (version, migrate) in migrations::<T>().iter().enumerate().skip(version as usize) {
+        value = migrate(value).map_err(|e|format!("version {version}: {e}"))?;
+    }
+    let Stored { metadata, pattern } = serde_json::from_value(value).map_err(|e|format!("version {VERSION}: {e}"))?;
+    let pattern = pattern.line().map_err(|e|format!("version {VERSION}: {e}"))?;
+    Ok(Document { metadata, pattern })
+}
+
+pub fn save_to_string<T: ColorTrait + Rgba>(document: &Document<T>, format: Format) -> Result<String, String>
+where Color: From<T> {
+    let Document { metadata, pattern } = document;
+    match format {
+        Format::Beads => serde_json::to_string(&Envelope { version: VERSION, metadata, pattern: pattern.into() }),
+        Format::Legacy => serde_json::to_string(&LegacyLine::from(pattern)),
+        Format::JBead => return jbead::save(document),
+    }.map_err(|e|e.to_string())
+}
+
+fn load_to_string(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let mut buf = String::new();
+    file.read_to_string(&mut buf).map_err(|e|e.to_string())?;
+    Ok(buf)
+}
+
+pub fn load_file<T: ColorTrait + Rgba + Serialize + DeserializeOwned + From<Color>>(path: &Path) -> Result<Document<T>, String> {
+    let s = load_to_string(path)?;
+    load_document(&s)
+}
+
+pub fn save<T: ColorTrait + Rgba>(path: &Path, document: &Document<T>, format: Format) -> Result<(), String>
+where Color: From<T> {
+    let mut file = File::create(path)
+        .map_err(|e|e.to_string())?;
+    let serialized = save_to_string(document, format)?;
+    file.write_all(serialized.as_bytes())
+        .map_err(|e|e.to_string())
+}
+
+/// Beads copied to the clipboard, colors row by row
+#[derive(Serialize, Deserialize)]
+struct Clip<T> {
+    width: usize,
+    beads: Vec<T>,
+}
+
+pub fn grid_to_string<T: ColorTrait + Serialize>(grid: &SimplifiedGrid<T>) -> Result<String, String> {
+    let beads = grid.as_table_iter().flatten().cloned().collect();
+    serde_json::to_string(&Clip { width: grid.size().width(), beads }).map_err(|e|e.to_string())
+}
+
+pub fn grid_from_string<T: ColorTrait + DeserializeOwned>(s: &str) -> Result<SimplifiedGrid<T>, String> {
+    let Clip { width, beads } = serde_json::from_str(s).map_err(|e|e.to_string())?;
+    let width = NonZeroUsize::new(width).ok_or("empty clip")?;
+    if beads.is_empty() || beads.len() % width.get() != 0 {
+        return Err(format!("{} beads do not make rows of {width}", beads.len()));
+    }
+    Ok(SimplifiedGrid::from_raw(width, beads))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::Coord;
+
+    fn written(line: &BeadsLine<Color>, format: Format) -> String {
+        save_to_string(&Document::from(line.clone()), format).unwrap()
+    }
+
+    fn read(s: &str) -> BeadsLine<Color> {
+        load_document(s).unwrap().pattern
+    }
+
+    #[test]
+    fn legacy_roundtrip() {
+        let mut line = BeadsLine::<Color>::default();
+        line.set_value(Color {r: 255, g: 0, b: 0}, Coord { x: 3, y: 5 });
+        let legacy = written(&line, Format::Legacy);
+        let loaded = read(&legacy);
+        assert_eq!(line.line(), loaded.line());
+        let actual = written(&loaded, Format::Beads);
+        assert_eq!(actual, written(&line, Format::Beads));
+    }
+
+    #[test]
+    fn legacy_progress_is_kept() {
+        let mut line = BeadsLine::<Color>::default();
+        line.set_value(Color {r: 255, g: 0, b: 0}, Coord { x: 3, y: 5 });
+        line.set_progress(504);
+        let legacy = written(&line, Format::Legacy);
+        assert!(legacy.contains(r#""filled":true"#));
+        assert_eq!(504, read(&legacy).progress());
+        line.set_progress(600);
+        let legacy = written(&line, Format::Legacy);
+        assert_eq!(504, read(&legacy).progress());
+        let actual = written(&line, Format::Beads);
+        assert_eq!(600, read(&actual).progress());
+    }
+
+    #[test]
+    fn symbols_are_saved() {
+        let mut line = BeadsLine::<Color>::default();
+        let red = Color {r: 255, g: 0, b: 0};
+        line.set_value(red, Coord { x: 3, y: 5 });
+        line.symbols.assign(red, "♥");
+        line.grow(crate::model::Side::Left);
+        let saved = written(&line, Format::Beads);
+        let loaded = read(&saved);
+        assert_eq!(Some("♥"), loaded.symbols.get(&red));
+        assert_eq!("A", loaded.legend()[&Color::default()]);
+        // files before the symbols were the bare pattern
+        let old = serde_json::to_string(&line).unwrap().replace(r#","symbols":[[{"r":255,"g":0,"b":0},"♥"]]"#, "");
+        assert!(read(&old).symbols.get(&red).is_none());
+    }
+
+    #[test]
+    fn historical_files_are_migrated() {
+        let red = Color {r: 255, g: 0, b: 0};
+        // the first versions: rgb colors, named schema, progress as flags
+        let first = r#"{"width":2,"line":[[{"color":{"r":255,"g":0,"b":0},"filled":true},3],[{"color":{"r":255,"g":255,"b":255},"filled":false},1]],"schema":"FirstOffset"}"#;
+        // the progress on its own, still without the layout and the symbols
+        let later = r#"{"width":2,"line":[[{"color":{"r":255,"g":0,"b":0}},3],[{"color":{"r":255,"g":255,"b":255}},1]],"schema":{"base_offset":2,"offset_step":1},"progress":3}"#;
+        for file in [first, later] {
+            let document: Document<Color> = load_document(file).unwrap();
+            assert_eq!(Metadata::default(), document.metadata);
+            let pattern = document.pattern;
+            assert_eq!((2, 2, 3), (pattern.width(), pattern.calculate_height(), pattern.progress()));
+            assert_eq!(Schema::default(), pattern.schema);
+            assert_eq!(3, pattern.summary()[&red]);
+        }
+    }
+
+    #[test]
+    fn metadata_is_saved() {
+        let mut document = Document::from(BeadsLine::<Color>::default());
+        document.metadata.title = "rope".to_string();
+        document.metadata.bead_size = "11/0".to_string();
+        document.metadata.touch(1700000000);
+        let saved = save_to_string(&document, Format::Beads).unwrap();
+        assert!(saved.starts_with(r#"{"version":2,"metadata":{"title":"rope""#));
+        let loaded: Document<Color> = load_document(&saved).unwrap();
+        assert_eq!(document.metadata, loaded.metadata);
+        assert_eq!(saved, save_to_string(&loaded, Format::Beads).unwrap());
+    }
+
+    #[test]
+    fn load_errors_are_clear() {
+        let error = |s: &str| load_document::<Color>(s).unwrap_err();
+        assert!(error("beads").starts_with("not a pattern file"));
+        assert_eq!("version 3 is newer than the supported 2", error(r#"{"version":3}"#));
+        assert_eq!("wrong version: \"1\"", error(r#"{"version":"1"}"#));
+        assert_eq!("version 0: missing field `width`", error(r#"{"line":[]}"#));
+        assert_eq!("version 1: missing field `pattern`", error(r#"{"version":1,"metadata":{}}"#));
+    }
+
+    #[test]
+    fn colors_are_written_as_hex() {
+        let red = Color {r: 255, g: 0, b: 0};
+        let mut line = BeadsLine::from_runs(2, vec![(red, 3), (Color::default(), 1)], Schema::default(), Layout::Loom);
+        line.symbols.assign(red, "♥");
+        let saved = written(&line, Format::Beads);
+        assert!(saved.contains(r##""colors":["#FF0000","#FFFFFF"],"line":[[0,3],[1,1]],"symbols":[[0,"♥"]]"##));
+        let first = json!({ "version": 1, "pattern": line }).to_string();
+        assert_eq!(saved, written(&read(&first), Format::Beads));
+        let error = |s: String| load_document::<Color>(&s).unwrap_err();
+        assert_eq!("version 2: wrong color red, expected #RRGGBB or #RRGGBBAA", error(saved.replace("#FF0000", "red")));
+        assert_eq!("version 2: unknown color 2", error(saved.replace("[1,1]", "[2,1]")));
+    }
+
+    #[test]
+    fn broken_patterns_are_rejected() {
+        let line = BeadsLine::from_runs(2, vec![(Color {r: 255, g: 0, b: 0}, 3), (Color::default(), 1)], Schema::default(), Layout::Tube);
+        let saved = written(&line, Format::Beads);
+        let error = |s: String| load_document::<Color>(&s).unwrap_err();
+        assert_eq!("version 2: base offset must be from 1 to 16", error(saved.replace(r#""base_offset":2"#, r#""base_offset":0"#)));
+        assert_eq!("version 2: offset step must be less than the base offset", error(saved.replace(r#""offset_step":1"#, r#""offset_step":2"#)));
+        assert_eq!("version 2: empty run 2", error(saved.replace("[[0,3],[1,1]]", "[[0,4],[1,0]]")));
+        assert_eq!("version 2: 5 beads are not whole rows of 2", error(saved.replace("[[0,3],[1,1]]", "[[0,3],[1,2]]")));
+        let unversioned = serde_json::to_string(&line).unwrap();
+        assert_eq!("version 0: base offset must be from 1 to 16", error(unversioned.replace(r#""base_offset":2"#, r#""base_offset":0"#)));
+    }
+
+    #[test]
+    fn clip_roundtrip() {
+        let red = Color {r: 255, g: 0, b: 0};
+        let grid = SimplifiedGrid::from_raw(NonZeroUsize::new(2).unwrap(), vec![red, Color::default(), red, red]);
+        let saved = grid_to_string(&grid).unwrap();
+        let loaded: SimplifiedGrid<Color> = grid_from_string(&saved).unwrap();
+        assert_eq!(saved, grid_to_string(&loaded).unwrap());
+        assert!(grid_from_string::<Color>(r#"{"width":2,"beads":[{"r":1,"g":2,"b":3}]}"#).is_err());
+        assert!(grid_from_string::<Color>("not a clip").is_err());
+    }
+}
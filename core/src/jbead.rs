@@ -0,0 +1,276 @@
+//! Patterns of JBead: an s-expression with the color table and the rows of the straight draft.
+//! JBead crochets the rope with the half-bead spiral and counts the rows from the bottom
+use std::convert::TryFrom;
+use std::num::NonZeroUsize;
+
+use indexmap::IndexSet;
+
+use crate::io::Document;
+use crate::model::{grid::SimplifiedGrid, BeadsLine, ColorTrait, Layout, Metadata, Rgba, Schema, SchemaChange};
+
+/// Symbols of the colors JBead uses until they are changed
+const SYMBOLS: &str = "·abcdefghijklmnopqrstuvwxyz+-/\\*";
+
+/// Element of the file: a word or a number, a quoted text, or a list in parentheses
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Atom(String),
+    Text(String),
+    List(Vec<Node>),
+}
+
+impl Node {
+    /// First word of the list
+    fn name(&self) -> Option<&str> {
+        match self {
+            Node::List(nodes) => match nodes.first() {
+                Some(Node::Atom(name)) => Some(name),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+    /// Elements of the list after its name
+    fn args(&self) -> &[Node] {
+        match self {
+            Node::List(nodes) if !nodes.is_empty() => &nodes[1..],
+            _ => &[],
+        }
+    }
+    /// Inner list with the name
+    fn find(&self, name: &str) -> Option<&Node> {
+        self.args().iter().find(|node| node.name() == Some(name))
+    }
+    fn text(&self) -> Option<&str> {
+        match self {
+            Node::Text(text) => Some(text),
+            _ => None,
+        }
+    }
+    fn number(&self) -> Option<usize> {
+        match self {
+            Node::Atom(atom) => atom.parse().ok(),
+            _ => None,
+        }
+    }
+}
+
+fn parse(s: &str) -> Result<Node, String> {
+    let mut chars = s.chars().peekable();
+    let mut stack: Vec<Vec<Node>> = vec![Vec::new()];
+    while let Some(c) = chars.next() {
+        match c {
+            '(' => stack.push(Vec::new()),
+            ')' => {
+                let list = stack.pop().filter(|_| !stack.is_empty()).ok_or("unexpected )")?;
+                stack.last_mut().unwrap().push(Node::List(list));
+            },
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next().ok_or("unterminated text")? {
+                        '"' => break,
+                        '\\' => match chars.next().ok_or("unterminated text")? {
+                            'n' => text.push('\n'),
+                            'r' => text.push('\r'),
+                            't' => text.push('\t'),
+                            other => text.push(other),
+                        },
+                        other => text.push(other),
+                    }
+                }
+                stack.last_mut().unwrap().push(Node::Text(text));
+            },
+            c if c.is_whitespace() => {},
+            c => {
+                let mut atom = c.to_string();
+                while let Some(&next) = chars.peek() {
+                    if next.is_whitespace() || next == '(' || next == ')' || next == '"' {
+                        break;
+                    }
+                    atom.push(next);
+                    chars.next();
+                }
+                stack.last_mut().unwrap().push(Node::Atom(atom));
+            },
+        }
+    }
+    if stack.len() > 1 {
+        return Err("missing )".to_string());
+    }
+    stack.pop().unwrap().into_iter().next().ok_or_else(|| "empty file".to_string())
+}
+
+/// Whether the text looks like a JBead pattern
+pub fn is_jbead(s: &str) -> bool {
+    s.trim_start().starts_with("(jbb")
+}
+
+pub fn load<T: ColorTrait + Rgba>(s: &str) -> Result<Document<T>, String> {
+    let root = parse(s)?;
+    if root.name() != Some("jbb") {
+        return Err("not a jbead pattern".to_string());
+    }
+    let text = |name: &str| {
+        root.find(name).and_then(|node| node.args().first()).and_then(Node::text).unwrap_or_default().to_string()
+    };
+    let colors = root.find("colors").ok_or("missing colors")?.args().iter().enumerate().map(|(n, node)| {
+        let rgba: Vec<u8> = node.args().iter().filter_map(Node::number).filter_map(|c| u8::try_from(c).ok()).collect();
+        match (node.name(), rgba.as_slice()) {
+            (Some("rgb"), &[r, g, b]) => Ok(T::from_rgba([r, g, b, 255])),
+            (Some("rgb"), &[r, g, b, a]) => Ok(T::from_rgba([r, g, b, a])),
+            _ => Err(format!("wrong color {n}")),
+        }
+    }).collect::<Result<Vec<_>, String>>()?;
+    let mut rows = root.find("model").ok_or("missing model")?.args().iter().enumerate().map(|(n, node)| {
+        node.args().iter().map(|bead| match bead.number() {
+            Some(color) if color < colors.len() => Ok(color),
+            _ => Err(format!("unknown color in the row {n}")),
+        }).collect::<Result<Vec<_>, String>>()
+    }).collect::<Result<Vec<_>, String>>()?;
+    // the field of JBead is taller than the pattern, unused rows are left with the first color
+    while rows.len() > 1 && rows.last().unwrap().iter().all(|&color| color == 0) {
+        rows.pop();
+    }
+    let width = rows.first().map_or(0, Vec::len);
+    let width = NonZeroUsize::new(width).ok_or("empty model")?;
+    if let Some(n) = rows.iter().position(|row| row.len() != width.get()) {
+        return Err(format!("row {n} has {} beads instead of {width}", rows[n].len()));
+    }
+    let data = rows.iter().rev().flatten().map(|&color| colors[color].clone()).collect();
+    let mut pattern = BeadsLine::from_simplified_grid(SimplifiedGrid::from_raw(width, data), Schema::new(2, 1)?);
+    let symbols = root.find("view").and_then(|view| view.find("symbols"))
+        .and_then(|node| node.args().first()).and_then(Node::text).unwrap_or(SYMBOLS);
+    let used = pattern.summary();
+    for (color, symbol) in colors.iter().zip(symbols.chars()) {
+        if used.contains_key(color) && !symbol.is_whitespace() {
+            pattern.symbols.assign(color.clone(), &symbol.to_string());
+        }
+    }
+    let metadata = Metadata { author: text("author"), notes: text("notes"), ..Default::default() };
+    Ok(Document { metadata, pattern })
+}
+
+fn quoted(text: &str) -> String {
+    let escaped = text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n");
+    format!("\"{escaped}\"")
+}
+
+/// JBead pattern of the document, the other schemas and the flat layouts are redrawn with the half-bead spiral
+pub fn save<T: ColorTrait + Rgba>(document: &Document<T>) -> Result<String, String> {
+    let mut line = document.pattern.clone();
+    let schema = Schema::new(2, 1)?;
+    if line.layout != Layout::Tube {
+        line.change_layout(Layout::Tube);
+    }
+    if line.schema != schema {
+        line.change_schema(schema, SchemaChange::KeepPicture);
+    }
+    // the first color of JBead is the background left in the unused rows
+    let legend = line.legend();
+    let background = T::default();
+    let colors: IndexSet<&T> = std::iter::once(&background).chain(legend.keys()).collect();
+    let symbols: String = colors.iter()
+        .map(|color| legend.get(*color).and_then(|symbol| symbol.chars().next()).unwrap_or('·'))
+        .collect();
+    let mut out = String::from("(jbb\n  (version 1)\n");
+    out += &format!("  (author {})\n  (organization \"\")\n", quoted(&document.metadata.author));
+    out += &format!("  (notes {})\n  (colors", quoted(&document.metadata.notes));
+    for color in &colors {
+        out += &match color.to_rgba() {
+            [r, g, b, 255] => format!("\n    (rgb {r} {g} {b})"),
+            [r, g, b, a] => format!("\n    (rgb {r} {g} {b} {a})"),
+        };
+    }
+    out += ")\n  (view\n    (draft-visible true)\n    (corrected-visible true)\n    (simulation-visible true)\n";
+    out += "    (report-visible true)\n    (selected-tool \"pencil\")\n    (selected-color 1)\n";
+    out += "    (zoom 2)\n    (scroll 0)\n    (shift 0)\n    (draw-colors true)\n    (draw-symbols false)\n";
+    out += &format!("    (symbols {}))\n  (model", quoted(&symbols));
+    let grid = line.simplified_grid();
+    for row in grid.as_table_iter().collect::<Vec<_>>().into_iter().rev() {
+        out += "\n    (row";
+        for color in row {
+            out += &format!(" {}", colors.get_index_of(color).unwrap());
+        }
+        out += ")";
+    }
+    out += "))\n";
+    Ok(out)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::Color;
+
+    const PATTERN: &str = r#"(jbb
+  (version 1)
+  (author "Anna \"the\" beader")
+  (organization "")
+  (notes "two\nlines")
+  (colors
+    (rgb 255 255 255)
+    (rgb 200 0 0)
+    (rgb 0 0 255))
+  (view
+    (zoom 2)
+    (symbols "·xo"))
+  (model
+    (row 1 1 2 0)
+    (row 0 2 0 1)
+    (row 0 0 0 0)))
+"#;
+
+    #[test]
+    fn jbead_is_loaded() {
+        let red = Color {r: 200, g: 0, b: 0};
+        let document: Document<Color> = load(PATTERN).unwrap();
+        assert_eq!("Anna \"the\" beader", document.metadata.author);
+        assert_eq!("two\nlines", document.metadata.notes);
+        let pattern = &document.pattern;
+        assert_eq!((4, 2), (pattern.width(), pattern.calculate_height()));
+        assert_eq!(Schema::new(2, 1).unwrap(), pattern.schema);
+        let rows: Vec<Vec<Color>> = pattern.simplified_grid().as_table_iter().map(|row| row.copied().collect()).collect();
+        assert_eq!(vec![Color::default(), Color {r: 0, g: 0, b: 255}, Color::default(), red], rows[0]);
+        assert_eq!(vec![red, red, Color {r: 0, g: 0, b: 255}, Color::default()], rows[1]);
+        assert_eq!("x", pattern.legend()[&red]);
+        assert_eq!("·", pattern.legend()[&Color::default()]);
+    }
+
+    #[test]
+    fn jbead_roundtrip() {
+        let document: Document<Color> = load(PATTERN).unwrap();
+        let saved = save(&document).unwrap();
+        assert!(is_jbead(&saved));
+        let loaded: Document<Color> = load(&saved).unwrap();
+        assert_eq!(document.metadata, loaded.metadata);
+        assert_eq!(document.pattern.simplified_grid().as_table_iter().flatten().collect::<Vec<_>>(),
+            loaded.pattern.simplified_grid().as_table_iter().flatten().collect::<Vec<_>>());
+        assert_eq!(document.pattern.legend(), loaded.pattern.legend());
+        // rows of the first color are kept when it is not the background
+        let mut top = document.clone();
+        let red = Color {r: 200, g: 0, b: 0};
+        top.pattern.grow_top();
+        for x in 0..4 {
+            top.pattern.set_value(red, crate::model::Coord { x, y: 0 });
+        }
+        let loaded: Document<Color> = load(&save(&top).unwrap()).unwrap();
+        assert_eq!(3, loaded.pattern.calculate_height());
+        // other schemas keep the picture
+        let mut straight = document.clone();
+        straight.pattern.change_schema(Schema::new(1, 0).unwrap(), SchemaChange::Reinterpret);
+        let loaded: Document<Color> = load(&save(&straight).unwrap()).unwrap();
+        assert_eq!(Schema::new(2, 1).unwrap(), loaded.pattern.schema);
+    }
+
+    #[test]
+    fn jbead_errors() {
+        let error = |s: &str| load::<Color>(s).unwrap_err();
+        assert_eq!("missing )", error("(jbb (colors (rgb 1 2 3))"));
+        assert_eq!("not a jbead pattern", error("(dbb)"));
+        assert_eq!("missing model", error("(jbb (colors (rgb 1 2 3)))"));
+        assert_eq!("unknown color in the row 1", error("(jbb (colors (rgb 1 2 3)) (model (row 0) (row 1)))"));
+        assert_eq!("row 1 has 1 beads instead of 2", error("(jbb (colors (rgb 1 2 3) (rgb 4 5 6)) (model (row 0 0) (row 1)))"));
+        assert_eq!("wrong color 0", error("(jbb (colors (rgb 1 2)) (model (row 0)))"));
+    }
+}
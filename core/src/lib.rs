@@ -0,0 +1,11 @@
+//! Pattern model of rusty-beads without any gui dependencies.
+//! Conversions for `ecolor::Color32` are available with the `ecolor` feature,
+//! writing rendered pictures as png with the `png` feature.
+pub mod wrapper;
+pub mod model;
+pub mod io;
+pub mod raster;
+pub mod chart;
+pub mod jbead;
+pub mod palette;
+pub mod catalog;
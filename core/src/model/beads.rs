@@ -0,0 +1,709 @@
+use std::ops::Range;
+
+use indexmap::IndexMap;
+
+use crate::wrapper::{Compressable, Chunkable};
+
+use super::{*, grid::SimplifiedGrid, runs::Runs};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(from = "BeadsLineCompat<T>")]
+pub struct BeadsLine<T: Eq + Hash + Clone + Debug + Default> {
+    pub width: usize,
+    pub(super) line: Runs<T>,
+    pub schema: Schema,
+    pub layout: Layout,
+    pub symbols: Symbols<T>,
+    /// count of already strung beads
+    pub(super) progress: usize,
+}
+
+/// Run of the versions keeping the progress as the `filled` flag of every run
+#[derive(Deserialize)]
+struct RunCompat<T> {
+    color: T,
+    #[serde(default)]
+    filled: bool,
+}
+
+#[derive(Deserialize)]
+struct BeadsLineCompat<T> {
+    width: usize,
+    line: Vec<(RunCompat<T>, usize)>,
+    schema: Schema,
+    #[serde(default)]
+    layout: Layout,
+    #[serde(default)]
+    symbols: Symbols<T>,
+    progress: Option<usize>,
+}
+
+impl<T: Eq + Hash + Clone + Debug + Default> From<BeadsLineCompat<T>> for BeadsLine<T> {
+    fn from(value: BeadsLineCompat<T>) -> Self {
+        let BeadsLineCompat { width, line, schema, layout, symbols, progress } = value;
+        let progress = progress.unwrap_or_else(|| {
+            line.iter().take_while(|(run, _)| run.filled).map(|(_, count)| count).sum()
+        });
+        let line = line.into_iter().map(|(run, count)| (Bead { color: run.color }, count)).collect();
+        Self { width, line, schema, layout, symbols, progress }
+    }
+}
+
+impl<T: Eq + Hash + Clone + Default + Debug> Default for BeadsLine<T> {
+    fn default() -> Self {
+        let width = 100;
+        let height = 100;
+        let line = vec![(T::default().into(), width * height)].into();
+        Self { width, line, schema: Default::default(), layout: Default::default(), symbols: Default::default(), progress: 0 }
+    }
+}
+
+impl <T: Default + Eq + Hash + Clone + Debug> BeadsLine<T> {
+    pub fn resize(&mut self, size: Size) {
+        let mut grid = self.simplified_grid();
+        grid.resize(size);
+        self.rebuild(grid);
+    }
+
+    /// Lays the picture out in the other technique, the stringing order is recomputed
+    pub fn change_layout(&mut self, layout: Layout) {
+        let grid = self.simplified_grid();
+        self.layout = layout;
+        self.rebuild(grid);
+    }
+
+    pub fn change_schema(&mut self, schema: Schema, mode: SchemaChange) {
+        match mode {
+            SchemaChange::Reinterpret => self.schema = schema,
+            SchemaChange::KeepPicture => {
+                let grid = self.resampled_grid(schema);
+                self.schema = schema;
+                self.rebuild(grid);
+            }
+        }
+    }
+
+    /// Picture of the pattern laid out in the other schema: every bead takes the color
+    /// of the bead under its center, rows are shifted by the fractional offsets of both schemas
+    fn resampled_grid(&self, schema: Schema) -> SimplifiedGrid<T> {
+        let width = self.width as isize;
+        let old = self.schema;
+        // positions are measured in halves of the smallest common fraction of a bead
+        let unit = (old.base() * schema.base()) as isize;
+        let shift = |schema: Schema, row| (2 * unit * schema.calculate_offset(row) as isize) / schema.base() as isize;
+        let source = self.simplified_grid();
+        let data = source.as_table_iter().enumerate().flat_map(|(row, beads)| {
+            let beads: Vec<_> = beads.collect();
+            let delta = shift(schema, row) - shift(old, row);
+            (0..width).map(move |pos| {
+                let center = (2 * pos + 1) * unit + delta;
+                beads[center.div_euclid(2 * unit).rem_euclid(width) as usize].clone()
+            })
+        }).collect();
+        SimplifiedGrid::from_raw(source.size().width, data)
+    }
+
+    pub fn grow(&mut self, side: Side) {
+        match side {
+            Side::Top => self.grow_top(),
+            side => {
+                let mut grid = self.simplified_grid();
+                grid.grow(side, T::default());
+                self.rebuild(grid);
+            }
+        }
+    }
+    pub fn shrink(&mut self, side: Side) {
+        match side {
+            Side::Top => self.shrink_top(),
+            side => {
+                let mut grid = self.simplified_grid();
+                if grid.shrink(side).is_err() {
+                    return;
+                }
+                self.rebuild(grid);
+            }
+        }
+    }
+
+    pub fn simplified_grid(&self) -> SimplifiedGrid<T> {
+        let data = self.table(0, 0)
+        .flat_map(|br|br.iter).map(|(_, obj)|obj.color.clone()).collect();
+        SimplifiedGrid::from_raw(NonZeroUsize::new(self.width).unwrap(), data)
+    }
+
+    pub fn from_simplified_grid(grid: SimplifiedGrid<T>, schema: Schema) -> Self {
+        Self::from_layout_grid(grid, schema, Layout::Tube)
+    }
+
+    /// Pattern of the runs of colors following each other in the stringing order
+    pub fn from_runs(width: usize, runs: impl IntoIterator<Item=(T, usize)>, schema: Schema, layout: Layout) -> Self {
+        let line = runs.into_iter().map(|(color, count)| (Bead { color }, count)).collect();
+        Self { width, line, schema, layout, symbols: Default::default(), progress: 0 }
+    }
+
+    /// Pattern showing the grid in the layout
+    pub fn from_layout_grid(grid: SimplifiedGrid<T>, schema: Schema, layout: Layout) -> Self {
+        let width = grid.size().width();
+        let line = grid.as_table_iter().enumerate().flat_map(|(n, i)|{
+            let row: Box<dyn Iterator<Item=&T>> = match layout {
+                Layout::Tube => Box::new(i.rev().cycle().skip(schema.calculate_rotation(n, width, 0)).take(width)),
+                layout if layout.column(n, 0, width) == 0 => Box::new(i),
+                _ => Box::new(i.rev()),
+            };
+            row
+        }).compress()
+        .map(|(obj,count)|(obj.into(), count))
+        .collect();
+        
+        Self {width, line, schema, layout, symbols: Default::default(), progress: 0}
+    }
+
+    /// Replaces the beads keeping the schema, the symbols and the strung rows
+    fn rebuild(&mut self, grid: SimplifiedGrid<T>) {
+        let symbols = std::mem::take(&mut self.symbols);
+        let (rows, rest) = (self.progress / self.width, self.progress % self.width);
+        *self = Self::from_layout_grid(grid, self.schema, self.layout);
+        self.symbols = symbols;
+        self.set_progress(rows * self.width + rest.min(self.width));
+    }
+
+    pub fn progress(&self) -> usize {
+        self.progress
+    }
+    /// Sets the count of strung beads, limited by the size of the pattern
+    pub fn set_progress(&mut self, progress: usize) {
+        self.progress = progress.min(self.line.beads_count());
+    }
+
+    pub fn grow_top(&mut self) {
+        let progress = if self.progress > 0 { self.progress + self.width } else { 0 };
+        if self.layout.is_flat() {
+            // rows of the flat layouts are read by their parity, the picture is kept instead
+            let mut grid = self.simplified_grid();
+            grid.grow(Side::Top, T::default());
+            self.rebuild(grid);
+        } else {
+            self.line.push_front(T::default().into(), self.width);
+        }
+        self.set_progress(progress);
+    }
+
+    pub fn grow_bottom(&mut self) {
+        self.line.push_back(T::default().into(), self.width);
+    }
+
+    pub fn shrink_top(&mut self) {
+        let progress = self.progress.saturating_sub(self.width);
+        if self.layout.is_flat() {
+            let mut grid = self.simplified_grid();
+            if grid.shrink(Side::Top).is_err() {
+                return;
+            }
+            self.rebuild(grid);
+        } else {
+            self.line.truncate_front(self.width);
+        }
+        self.set_progress(progress);
+    }
+    pub fn shrink_bottom(&mut self) {
+        self.line.truncate_back(self.width);
+        self.set_progress(self.progress);
+    }
+    pub fn rotate(&mut self, rotation: isize) {
+        let mut grid = self.simplified_grid();
+        grid.rotate(rotation);
+        self.rebuild(grid);
+    }
+    pub fn calculate_height(&self) -> usize {
+        self.line.beads_count() / self.width
+    }
+}
+
+/// How the beads are treated when the schema is changed
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum SchemaChange {
+    /// the stringing sequence is kept, the picture is laid out anew
+    #[default]
+    Reinterpret,
+    /// the picture is kept, the stringing sequence is recomputed
+    KeepPicture,
+}
+
+pub struct BeadsRow<'a, T> {
+    pub row: usize,
+    /// offset of the row in the fractions of the schema, zero for the flat layouts
+    pub offset: usize,
+    pub iter: Box<dyn Iterator<Item=(usize, &'a T)> + 'a>,
+}
+
+impl<T: Eq + Hash + Clone + Debug + Default + ColorTrait> BeadsLine<T> {
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    fn normalize_rotation(&self, rot: isize) -> usize {
+        let width = self.width as isize;
+        let modulo = rot % width;
+        if modulo >= 0 { modulo as usize} else { (width + modulo) as usize }
+    }
+    pub fn table(&self, rotation: isize, skip_rows: usize) -> impl Iterator<Item=BeadsRow<'_, Bead<T>>> {
+        let rotation = self.normalize_rotation(rotation); 
+        let width = self.width;
+        let (schema, layout) = (self.schema, self.layout);
+        self.line.beads_from(skip_rows * width).chunks(width).enumerate().map(move |(mut row_num, chunk)|{
+            row_num += skip_rows;
+            let iter = chunk.into_iter().enumerate();
+            let (offset, iter): (_, Box<dyn Iterator<Item=_>>) = match layout {
+                Layout::Tube => {
+                    let rotation = schema.calculate_rotation(row_num, width, rotation);
+                    (schema.calculate_offset(row_num), Box::new(iter.rev().cycle().skip(rotation).take(width)))
+                },
+                layout if layout.column(row_num, 0, width) == 0 => (0, Box::new(iter)),
+                _ => (0, Box::new(iter.rev())),
+            };
+            BeadsRow {row: row_num, offset, iter }
+        })
+    }
+    /// Coordinate of the bead shown at the position of the row, the inverse of `table`
+    pub fn coord_at(&self, row: usize, pos: usize, rotation: isize) -> Coord {
+        let width = self.width;
+        if self.layout.is_flat() {
+            return Coord { x: self.layout.column(row, pos, width), y: row };
+        }
+        let rotation = self.schema.calculate_rotation(row, width, self.normalize_rotation(rotation));
+        Coord { x: width - 1 - (pos + rotation) % width, y: row }
+    }
+    /// Position in the row of the bead as shown by `table`
+    pub fn pos_of(&self, Coord { x, y }: Coord, rotation: isize) -> usize {
+        let width = self.width;
+        if self.layout.is_flat() {
+            return self.layout.column(y, x, width);
+        }
+        let rotation = self.schema.calculate_rotation(y, width, self.normalize_rotation(rotation));
+        (2 * width - 1 - x - rotation) % width
+    }
+    /// Top left corner of the bead shown at the position of the row, in beads
+    pub fn place(&self, row: usize, pos: usize) -> [f32; 2] {
+        self.layout.place(self.schema, row, pos)
+    }
+    /// Size of the pattern as shown, in beads
+    pub fn extent(&self) -> [f32; 2] {
+        self.layout.extent(self.schema, self.width, self.calculate_height())
+    }
+    /// Bead shown at the point given in beads from the top left corner
+    pub fn locate(&self, point: [f32; 2], rotation: isize) -> Option<Coord> {
+        let (row, pos) = self.layout.locate(self.schema, self.width, self.calculate_height(), point)?;
+        Some(self.coord_at(row, pos, rotation))
+    }
+    /// Beads of the seam are drawn rounded, flat layouts have no seam
+    pub fn is_seam(&self, x: usize) -> bool {
+        x == 0 && !self.layout.is_flat()
+    }
+    /// Beads touching the given one
+    pub fn neighbours(&self, coord: Coord) -> Vec<Coord> {
+        let (width, height) = (self.width, self.calculate_height());
+        if !self.layout.is_flat() {
+            return self.schema.neighbours(coord, width, height);
+        }
+        self.layout.flat_neighbours(coord.y, self.pos_of(coord, 0), width, height).into_iter()
+            .map(|(row, pos)| self.coord_at(row, pos, 0))
+            .collect()
+    }
+    pub fn set_value(&mut self, value: T, coord: Coord) -> Option<Bead<T>> {
+        if coord.x >= self.width {
+            return None;
+        }
+        self.line.set(coord.x + self.width * coord.y, value.into())
+    }
+    /// Beads of the same color connected with the given one through the neighbours
+    pub fn connected_area(&self, coord: Coord) -> Vec<Coord> {
+        let width = self.width;
+        let index = |Coord{x, y}: Coord| x + width * y;
+        let colors: Vec<_> = self.line.beads().map(|bead|&bead.color).collect();
+        let color = match colors.get(index(coord)) {
+            Some(color) => *color,
+            None => return Vec::new(),
+        };
+        let mut visited = vec![false; colors.len()];
+        visited[index(coord)] = true;
+        let mut stack = vec![coord];
+        let mut result = Vec::new();
+        while let Some(coord) = stack.pop() {
+            result.push(coord);
+            for next in self.neighbours(coord) {
+                let i = index(next);
+                if !visited[i] && colors[i] == color {
+                    visited[i] = true;
+                    stack.push(next);
+                }
+            }
+        }
+        result
+    }
+    /// Index of the run containing the bead
+    pub fn run_at(&self, coord: Coord) -> Option<usize> {
+        if coord.x >= self.width {
+            return None;
+        }
+        self.line.run_index(coord.x + self.width * coord.y)
+    }
+    /// Positions of the beads of the run in the stringing order
+    pub fn run_range(&self, run: usize) -> Option<Range<usize>> {
+        self.line.run_range(run)
+    }
+    /// Coordinates of the beads of the run as used by `table`
+    pub fn run_coords(&self, run: usize) -> Vec<Coord> {
+        let width = self.width;
+        self.run_range(run).into_iter().flatten().map(|i|Coord{x: i % width, y: i / width}).collect()
+    }
+    pub fn line(&self) -> &Runs<T> {
+        &self.line
+    }
+    pub fn line_mut(&mut self) -> &mut Runs<T> {
+        &mut self.line
+    }
+    pub fn summary(&self) -> IndexMap<T, usize> {
+        self.line.iter().fold(IndexMap::default(), |mut summary, (item, count)|{
+            if let Some(saved) = summary.get_mut(&item.color) {
+                *saved += count;
+            } else {
+                summary.insert(item.color.clone(), count);
+            }
+            summary
+        })
+    }
+
+    /// Legend symbol of every color in the order of `summary`
+    pub fn legend(&self) -> IndexMap<T, String> {
+        self.symbols.legend(self.summary().keys())
+    }
+
+    pub fn map<X: Debug + Hash + Eq + Clone + Default, F: Fn(&T)->X>(&self, fun: F) -> BeadsLine<X> {
+        BeadsLine {
+            width: self.width,
+            schema: self.schema,
+            layout: self.layout,
+            line: self.line.map(|x|x.map(&fun)),
+            symbols: self.symbols.map(&fun),
+            progress: self.progress,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Hash, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Bead<T: ColorTrait> {
+    pub color: T,
+}
+
+impl<T: ColorTrait> Bead<T> {
+    fn map<X: ColorTrait>(&self, fun: impl Fn(&T) -> X) -> Bead<X> {
+        Bead {color: fun(&self.color)}
+    }
+}
+
+impl<T: ColorTrait> From<&T> for Bead<T> {
+    fn from(color: &T) -> Self {
+        Bead{color: color.clone()}
+    }
+}
+impl<T: ColorTrait> From<T> for Bead<T> {
+    fn from(color: T) -> Self {
+        Bead {color}
+    }
+}
+
+impl<T: ColorTrait + Default> Default for Bead<T> {
+    fn default() -> Self {
+        Bead {color: T::default()}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashMap;
+    use std::time::Instant;
+    use super::*;
+    use crate::wrapper::Uncompressable;
+    use rand::Rng;
+
+    struct X<T>(Vec<(T, usize)>);
+
+    impl<T> X<T> {
+        fn twisted_iter_table(&self, width: usize, revert_chunk: bool) -> impl Iterator<Item=impl Iterator<Item=&T>> {
+            self.0.iter().uncompress().chunks(width)
+            .enumerate()
+            .map(move |(n, chunk)|{
+                let iter = chunk.into_iter();
+                let x: Box<dyn Iterator<Item=&T>> = if revert_chunk {
+                   Box::new( twist_iterator(iter.rev(), n, width) )
+                } else {
+                    Box::new(twist_iterator(iter, n, width))
+                };
+                x
+            })
+        }
+    }
+
+    fn twist_iterator<I:Iterator+Clone>(iter: I, n: usize, width: usize) -> impl Iterator<Item = I::Item> {
+        iter.cycle().skip(n%width).take(width)
+    }
+
+    #[test]
+    fn test_twisted() {
+        let x = X((1..10).map(|x|(x,1)).collect());
+        let twisted:Vec<_> = x.twisted_iter_table(3,false).flatten().copied().collect();
+        assert_eq!(twisted, vec![1,2,3,5,6,4,9,7,8]);
+        let twisted_rev: Vec<_> = x.twisted_iter_table(3,true).flatten().copied().collect();
+        assert_eq!(twisted_rev, vec![3,2,1,5,4,6,7,9,8])
+    }
+
+
+    #[test] 
+    fn load_test() {
+        let v = (0..2000u32).map(|x|(x, 100)).collect();
+        let x = X(v);
+        let start = Instant::now();
+        for _ in 0..10 {
+            let table = x.twisted_iter_table(5,true);
+            let v = table.fold(Vec::new(), |mut v, iter| {
+                v.push(
+                    iter.fold(Vec::with_capacity(5), |mut v, x|{
+                        v.push(*x);
+                        v
+                    }).len()
+                );
+                v
+            });
+            v.len();
+        }
+        println!("with iters: {:?}", start.elapsed());
+        let start = Instant::now();
+        for _ in 0..10 {
+            let table = x.twisted_iter_table(50,true);
+            let v = table.fold(Vec::new(), |mut v, iter| {
+                v.push(
+                    iter.fold(Vec::with_capacity(50), |mut v, x|{
+                        v.push(*x);
+                        v
+                    }).len()
+                );
+                v
+            });
+            v.len();
+        }
+        println!("with data: {:?}", start.elapsed());
+    }
+
+    /// Pattern of the size filled with the color 0
+    fn blank(width: usize, height: usize, schema: Schema) -> BeadsLine<u32> {
+        BeadsLine::from_runs(width, [(0, width * height)], schema, Layout::default())
+    }
+
+    #[test]
+    fn test_from_grid() {
+        let width = 40;
+        let mut rng = rand::thread_rng();
+        let x = (0..(width*width)).map(|_|rng.gen_range(0..10u32).into()).compress();
+        let line = BeadsLine { width, line: x.collect::<Vec<_>>().into(), ..Default::default() };
+        let line_backup =line.clone();
+        let grid = line.simplified_grid();
+        let line = BeadsLine::from_simplified_grid(grid, Default::default());
+        
+        assert_eq!(line_backup.line, line.line)
+
+    }
+
+    #[test]
+    fn test_neighbours_match_table() {
+        let (width, height) = (9, 6);
+        for (base_offset, offset_step) in [(1, 0), (2, 1), (3, 1), (4, 1), (7, 3)] {
+            let schema: Schema = serde_json::from_str(
+                &format!(r#"{{"base_offset":{base_offset},"offset_step":{offset_step}}}"#)
+            ).unwrap();
+            let line = blank(width, height, schema);
+            let mut positions = HashMap::new();
+            for BeadsRow { row, offset, iter } in line.table(0, 0) {
+                for (pos, (x, _)) in iter.enumerate() {
+                    positions.insert(Coord{x, y: row}, (pos * base_offset + offset) as isize);
+                }
+            }
+            let round = (width * base_offset) as isize;
+            let touches = |a: Coord, b: Coord| {
+                let dist = (positions[&a] - positions[&b]).rem_euclid(round);
+                let dist = dist.min(round - dist);
+                match a.y as isize - b.y as isize {
+                    0 => dist == base_offset as isize,
+                    -1 | 1 => dist < base_offset as isize,
+                    _ => false,
+                }
+            };
+            for &a in positions.keys() {
+                let mut expected: Vec<_> = positions.keys().copied().filter(|&b|touches(a, b)).collect();
+                let mut actual = schema.neighbours(a, width, height);
+                expected.sort_by_key(|c|(c.y, c.x));
+                actual.sort_by_key(|c|(c.y, c.x));
+                assert_eq!(expected, actual, "schema {schema}, bead {a:?}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_connected_area_wraps_seam() {
+        let width = 10;
+        let mut line = blank(width, 4, Default::default());
+        for x in 2..width-1 {
+            for y in 0..4 {
+                line.set_value(1, Coord{x, y});
+            }
+        }
+        let mut area = line.connected_area(Coord{x: 0, y: 2});
+        area.sort_by_key(|c|(c.y, c.x));
+        let mut expected: Vec<_> = (0..4).flat_map(|y|[0, 1, width-1].map(|x|Coord{x, y})).collect();
+        expected.sort_by_key(|c|(c.y, c.x));
+        assert_eq!(expected, area);
+    }
+
+    #[test]
+    fn test_progress_survives_edits() {
+        let width = 10;
+        let mut line = blank(width, 6, Default::default());
+        line.set_progress(25);
+        line.set_value(1, Coord{x: 3, y: 0});
+        line.set_value(2, Coord{x: 7, y: 4});
+        line.rotate(3);
+        assert_eq!(25, line.progress());
+        line.grow_top();
+        assert_eq!(35, line.progress());
+        line.shrink_top();
+        line.shrink_top();
+        assert_eq!(15, line.progress());
+        line.grow(Side::Right);
+        assert_eq!(16, line.progress());
+        line.set_progress(1000);
+        assert_eq!(55, line.progress());
+        line.shrink_bottom();
+        assert_eq!(44, line.progress());
+    }
+
+    #[test]
+    fn test_runs_match_table() {
+        let width = 7;
+        let mut line = blank(width, 5, Default::default());
+        for (n, &x) in [1, 2, 3, 5].iter().enumerate() {
+            line.set_value(n as u32 + 1, Coord{x, y: n});
+        }
+        for BeadsRow { row, iter, .. } in line.table(3, 0) {
+            for (x, bead) in iter {
+                let coord = Coord{x, y: row};
+                let run = line.run_at(coord).unwrap();
+                assert!(line.run_range(run).unwrap().contains(&(x + width * row)));
+                assert_eq!(line.line.get(x + width * row), Some(bead));
+                assert!(line.run_coords(run).contains(&coord));
+            }
+        }
+        assert_eq!(Some(0..1), line.run_range(0));
+        assert_eq!(Some(2..9), line.run_range(2));
+        assert_eq!(vec![Coord{x: 1, y: 0}], line.run_coords(1));
+        assert_eq!(None, line.run_at(Coord{x: 0, y: 5}));
+        assert_eq!(None, line.run_range(9));
+    }
+
+    #[test]
+    fn test_coord_at_inverts_table() {
+        let (width, height) = (9, 6);
+        for (base_offset, offset_step) in [(1, 0), (2, 1), (3, 2)] {
+            let schema: Schema = serde_json::from_str(
+                &format!(r#"{{"base_offset":{base_offset},"offset_step":{offset_step}}}"#)
+            ).unwrap();
+            let line = blank(width, height, schema);
+            for rotation in [-11, -1, 0, 4, 9] {
+                for BeadsRow { row, iter, .. } in line.table(rotation, 0) {
+                    for (pos, (x, _)) in iter.enumerate() {
+                        assert_eq!(Coord{x, y: row}, line.coord_at(row, pos, rotation));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_schema_change_keeps_picture() {
+        let (width, height, column) = (12, 8, 5);
+        let data = (0..width*height).map(|i| (i % width == column) as u32).collect();
+        let grid = SimplifiedGrid::from_raw(NonZeroUsize::new(width).unwrap(), data);
+        let line = BeadsLine::from_simplified_grid(grid, Schema::default());
+        let stripe = |line: &BeadsLine<u32>| -> Vec<Vec<usize>> {
+            line.simplified_grid().as_table_iter()
+                .map(|row| row.enumerate().filter(|(_, color)| **color == 1).map(|(pos, _)| pos).collect())
+                .collect()
+        };
+
+        let mut same = line.clone();
+        same.change_schema(line.schema, SchemaChange::KeepPicture);
+        assert_eq!(line.line, same.line);
+
+        let mut schema = line.schema;
+        for _ in 0..4 {
+            schema = schema.switch();
+            let mut kept = line.clone();
+            kept.change_schema(schema, SchemaChange::KeepPicture);
+            for positions in stripe(&kept) {
+                assert_eq!(1, positions.len(), "schema {schema}");
+                assert!(positions[0].abs_diff(column) <= 1, "schema {}", schema);
+            }
+        }
+        let mut reinterpreted = line.clone();
+        reinterpreted.change_schema(schema, SchemaChange::Reinterpret);
+        assert_eq!(line.line, reinterpreted.line);
+        assert_ne!(stripe(&line), stripe(&reinterpreted));
+    }
+
+    #[test]
+    fn test_flat_layouts() {
+        let (width, height) = (6, 5);
+        let data = (0..(width*height) as u32).collect();
+        let grid = SimplifiedGrid::from_raw(NonZeroUsize::new(width).unwrap(), data);
+        for layout in LAYOUTS {
+            let mut line = BeadsLine::from_simplified_grid(grid.clone(), Schema::default());
+            line.change_layout(layout);
+            assert_eq!(grid.as_table_iter().flatten().collect::<Vec<_>>(), line.simplified_grid().as_table_iter().flatten().collect::<Vec<_>>());
+            let rotation = if layout.is_flat() { 0 } else { 2 };
+            let mut places = HashMap::new();
+            for BeadsRow { row, iter, .. } in line.table(rotation, 0) {
+                for (pos, (x, _)) in iter.enumerate() {
+                    let coord = Coord{x, y: row};
+                    assert_eq!(coord, line.coord_at(row, pos, rotation));
+                    assert_eq!(pos, line.pos_of(coord, rotation));
+                    let [left, top] = line.place(row, pos);
+                    assert_eq!(Some(coord), line.locate([left + 0.5, top + 0.5], rotation), "{layout}");
+                    places.insert(coord, [left, top]);
+                }
+            }
+            if !layout.is_flat() {
+                continue;
+            }
+            // flat beads touch when their squares share a part of a side
+            let touches = |a: [f32; 2], b: [f32; 2]| {
+                let (dx, dy) = ((a[0] - b[0]).abs(), (a[1] - b[1]).abs());
+                (dx == 1.0 && dy < 1.0) || (dy == 1.0 && dx < 1.0)
+            };
+            for (&a, &place) in &places {
+                let mut expected: Vec<_> = places.iter().filter(|(_, &other)| touches(place, other)).map(|(&b, _)| b).collect();
+                let mut actual = line.neighbours(a);
+                expected.sort_by_key(|c|(c.y, c.x));
+                actual.sort_by_key(|c|(c.y, c.x));
+                assert_eq!(expected, actual, "{layout}, bead {a:?}");
+            }
+            let across = line.figure(Figure::Line, line.coord_at(2, 0, 0), line.coord_at(2, width - 1, 0));
+            let positions: Vec<_> = across.iter().map(|&coord| line.pos_of(coord, 0)).collect();
+            assert_eq!((0..width).collect::<Vec<_>>(), positions);
+        }
+        let mut line = BeadsLine::from_simplified_grid(grid, Schema::default());
+        line.change_layout(Layout::Peyote);
+        let json = serde_json::to_string(&line).unwrap();
+        let loaded: BeadsLine<u32> = serde_json::from_str(&json).unwrap();
+        assert_eq!(Layout::Peyote, loaded.layout);
+        let old = json.replace(r#","layout":"Peyote""#, "");
+        assert_eq!(Layout::Tube, serde_json::from_str::<BeadsLine<u32>>(&old).unwrap().layout);
+    }
+}
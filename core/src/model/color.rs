@@ -0,0 +1,163 @@
+#[cfg(feature = "ecolor")]
+use ecolor::Color32;
+
+use super::{Serialize, Deserialize, Rgba};
+use std::fmt;
+use std::num::ParseIntError;
+use std::str::FromStr;
+
+#[derive(Clone, Hash, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+}
+
+impl Default for Color {
+    fn default() -> Self {
+        Self {r:255,g:255,b:255}
+    }
+}
+
+impl fmt::UpperHex for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_hex(self))
+    }
+}
+
+impl fmt::LowerHex for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&to_hex(self).to_lowercase())
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseColorError {
+    WrongLen,
+    WrongFirstSymbol,
+    WrongDigit,
+    Parse(ParseIntError),
+    Encoding,
+}
+
+impl From<ParseIntError> for ParseColorError {
+    fn from(e: ParseIntError) -> Self {
+        ParseColorError::Parse(e)
+    }
+}
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseColorError::Parse(e) => write!(f, "{e}"),
+            _ => f.write_str("expected #RRGGBB or #RRGGBBAA"),
+        }
+    }
+}
+
+/// Color written as `#RRGGBB` or `#RRGGBBAA`, the alpha of the first one is opaque
+pub fn from_hex<T: Rgba>(s: &str) -> Result<T, ParseColorError> {
+    if s.len() != 7 && s.len() != 9 {
+        Err(ParseColorError::WrongLen)
+    } else if !s.is_ascii() {
+        Err(ParseColorError::Encoding)
+    } else if !s.starts_with('#') {
+        Err(ParseColorError::WrongFirstSymbol)
+    } else if !s[1..].bytes().all(|b| b.is_ascii_hexdigit()) {
+        // `from_str_radix` would take a sign
+        Err(ParseColorError::WrongDigit)
+    } else {
+        let byte = |at: usize| u8::from_str_radix(&s[at..at + 2], 16);
+        let alpha = if s.len() == 9 { byte(7)? } else { 255 };
+        Ok(T::from_rgba([byte(1)?, byte(3)?, byte(5)?, alpha]))
+    }
+}
+
+impl FromStr for Color {
+    type Err = ParseColorError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        from_hex(s)
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:X}", self)
+    }
+}
+
+/// `#RRGGBB` of an opaque color, `#RRGGBBAA` of a translucent one
+pub fn to_hex<T: Rgba>(color: &T) -> String {
+    match color.to_rgba() {
+        [r, g, b, 255] => format!("#{r:02X}{g:02X}{b:02X}"),
+        [r, g, b, a] => format!("#{r:02X}{g:02X}{b:02X}{a:02X}"),
+    }
+}
+
+impl Rgba for Color {
+    fn to_rgba(&self) -> [u8; 4] {
+        [self.r, self.g, self.b, 255]
+    }
+    fn from_rgba([r, g, b, _]: [u8; 4]) -> Self {
+        Self {r, g, b}
+    }
+}
+
+#[cfg(feature = "ecolor")]
+impl Rgba for Color32 {
+    fn to_rgba(&self) -> [u8; 4] {
+        self.to_srgba_unmultiplied()
+    }
+    fn from_rgba([r, g, b, a]: [u8; 4]) -> Self {
+        Color32::from_rgba_unmultiplied(r, g, b, a)
+    }
+}
+
+#[cfg(feature = "ecolor")]
+impl From<Color> for Color32 {
+    fn from(color: Color) -> Self {
+        let Color {r,g,b} = color;
+        Color32::from_rgb(r, g, b)
+    }
+}
+
+#[cfg(feature = "ecolor")]
+impl From<Color32> for Color {
+    fn from(color: Color32) -> Self {
+        let [r, g, b, _] = color.to_srgba_unmultiplied();
+        Self {r, g, b}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    struct Raw([u8; 4]);
+
+    impl Rgba for Raw {
+        fn to_rgba(&self) -> [u8; 4] {
+            self.0
+        }
+        fn from_rgba(rgba: [u8; 4]) -> Self {
+            Self(rgba)
+        }
+    }
+
+    #[test]
+    fn hex_roundtrip() {
+        let red = Color {r: 255, g: 0, b: 0};
+        assert_eq!("#FF0000", to_hex(&red));
+        assert_eq!(red, from_hex::<Color>("#ff0000").unwrap());
+        assert_eq!(red, from_hex::<Color>("#FF000080").unwrap());
+        assert_eq!(red, "#ff0000".parse().unwrap());
+        assert_eq!("#ff0000", format!("{:x}", red));
+        assert_eq!(Raw([1, 2, 3, 4]), from_hex("#01020304").unwrap());
+        assert_eq!("#01020304", to_hex(&Raw([1, 2, 3, 4])));
+        for wrong in ["FF0000", "#FF00", "#GG0000", "#FF00000", "#ФФ00", "#+F0000", "#0+0000", "#FF0000+F"] {
+            assert!(from_hex::<Color>(wrong).is_err(), "{}", wrong);
+        }
+    }
+}
@@ -2,6 +2,12 @@ use super::*;
 
 pub trait ColorTrait: Debug + Clone + Hash + Eq + PartialEq + Default {}
 
+/// Colors convertible to and from unmultiplied srgba
+pub trait Rgba {
+    fn to_rgba(&self) -> [u8; 4];
+    fn from_rgba(rgba: [u8; 4]) -> Self;
+}
+
 pub trait GetSchema {
     fn get_schema(&self) -> Schema;
 }
@@ -0,0 +1,331 @@
+use std::collections::BTreeMap;
+
+use indexmap::IndexSet;
+
+use super::*;
+
+/// Shapes drawn from one bead to another: the line joins them,
+/// the rectangle and the ellipse are inscribed into the box with these corners
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Figure {
+    Line,
+    Rectangle { filled: bool },
+    Ellipse { filled: bool },
+}
+
+/// Beads of a major line (a row, or a column of peyote) from the first to the last minor position
+type Span = (isize, isize, isize);
+
+const EPSILON: f32 = 1e-3;
+
+/// Shown beads as lines along the major axis, every line shifted along the minor one.
+/// Rows are the major lines, except peyote whose columns are shifted
+struct Lattice<'a, T: ColorTrait> {
+    line: &'a BeadsLine<T>,
+    transposed: bool,
+}
+
+impl<'a, T: ColorTrait> Lattice<'a, T> {
+    fn shift(&self, major: isize) -> f32 {
+        let major = major.max(0) as usize;
+        if self.transposed { self.line.place(0, major)[1] } else { self.line.place(major, 0)[0] }
+    }
+    /// Major and minor coordinates of the center of the bead shown at the position of the row
+    fn center(&self, row: usize, pos: usize) -> [f32; 2] {
+        let [x, y] = self.line.place(row, pos);
+        if self.transposed { [x + 0.5, y + 0.5] } else { [y + 0.5, x + 0.5] }
+    }
+    fn bead(&self, major: isize, minor: isize) -> (isize, isize) {
+        if self.transposed { (minor, major) } else { (major, minor) }
+    }
+    /// First and last bead of the major line with the center from the start of the minor range to its end,
+    /// a bead half over the end is left to the next range
+    fn centers_within(&self, major: isize, from: f32, to: f32) -> Option<Span> {
+        let shift = self.shift(major) + 0.5;
+        let (first, end) = ((from - shift - EPSILON).ceil() as isize, (to - shift - EPSILON).ceil() as isize);
+        (first < end).then_some((major, first, end - 1))
+    }
+    /// First and last bead of the major line touched by the minor range.
+    /// A flat line keeps the range running along its shifted end
+    fn touched(&self, major: isize, from: f32, to: f32) -> Span {
+        let shift = self.shift(major);
+        let mut first = (from - shift + EPSILON).floor() as isize;
+        let mut last = (to - shift - EPSILON).floor() as isize;
+        if self.line.layout.is_flat() {
+            let count = if self.transposed { self.line.calculate_height() } else { self.line.width } as isize;
+            first = first.clamp(0, count - 1);
+            last = last.clamp(0, count - 1);
+        }
+        (major, first, last.max(first))
+    }
+    /// Whether the span covers the whole minor range of the bead of the neighbouring line
+    fn covers(&self, spans: &[Span], major: isize, from: f32, to: f32) -> bool {
+        let shift = self.shift(major);
+        spans.iter().any(|&(m, first, last)| {
+            m == major && first as f32 + shift <= from + EPSILON && (last + 1) as f32 + shift >= to - EPSILON
+        })
+    }
+    /// Beads of the spans, only the ones at the border when not filled
+    fn outlined(&self, spans: Vec<Span>, filled: bool) -> Vec<(isize, isize)> {
+        let mut result = Vec::new();
+        for &(major, first, last) in &spans {
+            let shift = self.shift(major);
+            for minor in first..=last {
+                let (from, to) = (minor as f32 + shift, (minor + 1) as f32 + shift);
+                let inner = minor != first && minor != last
+                    && self.covers(&spans, major - 1, from, to)
+                    && self.covers(&spans, major + 1, from, to);
+                if filled || !inner {
+                    result.push(self.bead(major, minor));
+                }
+            }
+        }
+        result
+    }
+}
+
+impl<T: ColorTrait> BeadsLine<T> {
+    /// Beads of the figure laid out on the beads as they are shown.
+    /// The tube is crossed the short way around the seam, flat layouts are cut at the edges
+    pub fn figure(&self, figure: Figure, start: Coord, end: Coord) -> Vec<Coord> {
+        if !self.layout.is_flat() {
+            return self.tube_figure(figure, start, end);
+        }
+        let lattice = &Lattice { line: self, transposed: self.layout == Layout::Peyote };
+        let a = lattice.center(start.y, self.pos_of(start, 0));
+        let b = lattice.center(end.y, self.pos_of(end, 0));
+        let majors = (a[0].min(b[0]).floor() as isize)..=(a[0].max(b[0]).floor() as isize);
+        let (low, high) = (a[1].min(b[1]), a[1].max(b[1]));
+        let shown = match figure {
+            Figure::Line => majors.flat_map(|major| {
+                // part of the segment crossing the major line
+                let (from, to) = ((major as f32).max(a[0].min(b[0])), ((major + 1) as f32).min(a[0].max(b[0])));
+                let minor = |at: f32| if a[0] == b[0] { None } else { Some(a[1] + (b[1] - a[1]) * (at - a[0]) / (b[0] - a[0])) };
+                let (x0, x1) = (minor(from).unwrap_or(low), minor(to).unwrap_or(high));
+                let (_, first, last) = lattice.touched(major, x0.min(x1), x0.max(x1));
+                (first..=last).map(move |minor| lattice.bead(major, minor))
+            }).collect(),
+            Figure::Rectangle { filled } => {
+                let spans = majors.filter_map(|major| lattice.centers_within(major, low - 0.5, high + 0.5)).collect();
+                lattice.outlined(spans, filled)
+            },
+            Figure::Ellipse { filled } => {
+                let center = [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0];
+                let radius = [(a[0] - b[0]).abs() / 2.0 + 0.5, (a[1] - b[1]).abs() / 2.0 + 0.5];
+                let spans = majors.filter_map(|major| {
+                    let across = (major as f32 + 0.5 - center[0]) / radius[0];
+                    let half = radius[1] * (1.0 - across * across).max(0.0).sqrt();
+                    lattice.centers_within(major, center[1] - half, center[1] + half)
+                }).collect();
+                lattice.outlined(spans, filled)
+            },
+        };
+        let (width, height) = (self.width as isize, self.calculate_height() as isize);
+        let coords: IndexSet<_> = shown.into_iter().filter_map(|(row, pos)| {
+            if row < 0 || row >= height || pos < 0 || pos >= width {
+                return None;
+            }
+            Some(self.coord_at(row as usize, pos as usize, 0))
+        }).collect();
+        coords.into_iter().collect()
+    }
+
+    /// Figure on the tube joining its corners, or the points of the ellipse, with the lines of the schema.
+    /// A filled figure takes every bead of a row between its outline
+    fn tube_figure(&self, figure: Figure, start: Coord, end: Coord) -> Vec<Coord> {
+        let width = self.width as isize;
+        let (vertices, filled) = match figure {
+            Figure::Line => return self.schema.make_line(start, end, self.width),
+            Figure::Rectangle { filled } => {
+                let corner = |row: Coord, column: Coord| self.coord_at(row.y, self.pos_of(column, 0), 0);
+                (vec![start, corner(start, end), end, corner(end, start), start], filled)
+            },
+            Figure::Ellipse { filled } => (self.ellipse_points(start, end), filled),
+        };
+        let mut coords: IndexSet<_> = vertices.first().copied().into_iter()
+            .chain(vertices.windows(2).flat_map(|pair| self.schema.make_line(pair[0], pair[1], self.width)))
+            .collect();
+        if filled {
+            // positions counted the short way from the middle of the figure
+            let short = |delta: isize| {
+                let delta = delta.rem_euclid(width);
+                if 2 * delta > width { delta - width } else { delta }
+            };
+            let from = self.pos_of(start, 0) as isize;
+            let middle = from + short(self.pos_of(end, 0) as isize - from) / 2;
+            let mut spans: BTreeMap<usize, (isize, isize)> = BTreeMap::new();
+            for &coord in &coords {
+                let pos = short(self.pos_of(coord, 0) as isize - middle);
+                let span = spans.entry(coord.y).or_insert((pos, pos));
+                *span = (span.0.min(pos), span.1.max(pos));
+            }
+            for (row, (first, last)) in spans {
+                coords.extend((first..=last).map(|pos| self.coord_at(row, (middle + pos).rem_euclid(width) as usize, 0)));
+            }
+        }
+        coords.into_iter().collect()
+    }
+
+    /// Beads under the ellipse through the centers of the box from the start to the end, going around it
+    fn ellipse_points(&self, start: Coord, end: Coord) -> Vec<Coord> {
+        let lattice = &Lattice { line: self, transposed: false };
+        let a = lattice.center(start.y, self.pos_of(start, 0));
+        let mut b = lattice.center(end.y, self.pos_of(end, 0));
+        let width = self.width as f32;
+        let delta = (b[1] - a[1]).rem_euclid(width);
+        b[1] = a[1] + if 2.0 * delta > width { delta - width } else { delta };
+        let center = [(a[0] + b[0]) / 2.0, (a[1] + b[1]) / 2.0];
+        let radius = [(a[0] - b[0]).abs() / 2.0, (a[1] - b[1]).abs() / 2.0];
+        let (top, bottom) = (start.y.min(end.y) as f32, start.y.max(end.y) as f32);
+        let steps = 8 * (radius[0] + radius[1]).ceil() as usize + 8;
+        let mut points: Vec<Coord> = (0..=steps).map(|step| {
+            let angle = 2.0 * std::f32::consts::PI * step as f32 / steps as f32;
+            let row = (center[0] + radius[0] * angle.cos()).floor().clamp(top, bottom) as isize;
+            let pos = (center[1] + radius[1] * angle.sin() - lattice.shift(row)).floor() as isize;
+            self.coord_at(row as usize, pos.rem_euclid(self.width as isize) as usize, 0)
+        }).collect();
+        points.dedup();
+        points
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+    use super::*;
+
+    fn pattern(layout: Layout, schema: Schema) -> BeadsLine<u32> {
+        let mut line = BeadsLine { schema, ..Default::default() };
+        line.change_layout(layout);
+        line.resize(Size { width: NonZeroUsize::new(16).unwrap(), height: NonZeroUsize::new(10).unwrap() });
+        line
+    }
+
+    /// Whether the shown beads are joined by touching sides or corners
+    fn connected(line: &BeadsLine<u32>, coords: &[Coord]) -> bool {
+        let width = line.width() as f32;
+        let touch = |a: Coord, b: Coord| {
+            let ([ax, ay], [bx, by]) = (line.place(a.y, line.pos_of(a, 0)), line.place(b.y, line.pos_of(b, 0)));
+            let dx = (ax - bx).abs();
+            let dx = if line.layout.is_flat() { dx } else { dx.min(width - dx) };
+            dx <= 1.0 + EPSILON && (ay - by).abs() <= 1.0 + EPSILON
+        };
+        let mut reached = vec![coords[0]];
+        let mut stack = vec![coords[0]];
+        while let Some(coord) = stack.pop() {
+            for &next in coords {
+                if touch(coord, next) && !reached.contains(&next) {
+                    reached.push(next);
+                    stack.push(next);
+                }
+            }
+        }
+        reached.len() == coords.len()
+    }
+
+    #[test]
+    fn lines_are_connected() {
+        let mut rng = rand::thread_rng();
+        let tubes = PRESETS.iter().map(|preset| (Layout::Tube, preset.schema));
+        let flat = LAYOUTS.iter().skip(1).map(|&layout| (layout, Schema::default()));
+        for (layout, schema) in tubes.chain(flat) {
+            let line = pattern(layout, schema);
+            for _ in 0..50 {
+                let mut coord = || Coord { x: rng.gen_range(0..16), y: rng.gen_range(0..10) };
+                let (start, end) = (coord(), coord());
+                let beads = line.figure(Figure::Line, start, end);
+                assert!(beads.contains(&start) && beads.contains(&end), "{} {} {:?} {:?}", layout, schema, start, end);
+                assert!(connected(&line, &beads), "{} {} {:?} {:?} {:?}", layout, schema, start, end, beads);
+            }
+        }
+    }
+
+    #[test]
+    fn straight_lines_take_a_bead_per_step() {
+        let line = pattern(Layout::Tube, Schema::new(1, 0).unwrap());
+        let diagonal = line.figure(Figure::Line, line.coord_at(2, 3, 0), line.coord_at(4, 5, 0));
+        assert_eq!(vec![line.coord_at(2, 3, 0), line.coord_at(3, 4, 0), line.coord_at(4, 5, 0)], diagonal);
+        let seam = line.figure(Figure::Line, line.coord_at(6, 14, 0), line.coord_at(6, 0, 0));
+        let positions: Vec<_> = seam.iter().map(|&coord| line.pos_of(coord, 0)).collect();
+        assert_eq!(vec![14, 15, 0], positions);
+    }
+
+    #[test]
+    fn lines_take_the_beads_of_the_schema_lines() {
+        let mut line = BeadsLine::<u32> { schema: Schema::new(1, 0).unwrap(), ..Default::default() };
+        line.resize(Size { width: NonZeroUsize::new(100).unwrap(), height: NonZeroUsize::new(30).unwrap() });
+        let cases: [&[(usize, usize)]; 10] = [
+            &[(94, 16), (93, 16), (92, 16)],
+            &[(91, 12), (91, 13), (91, 14)],
+            &[(95, 14), (94, 13), (93, 12)],
+            &[(89, 12), (88, 13), (87, 14)],
+            &[(92, 19), (91, 19), (90, 18), (89, 18)],
+            &[(97, 18), (96, 18), (95, 19), (94, 19)],
+            &[(95, 28), (94, 27), (93, 27), (92, 26)],
+            &[(95, 22), (94, 23), (93, 23), (92, 24)],
+            &[(90, 24), (89, 23), (89, 22), (88, 21)],
+            &[(86, 22), (85, 23), (85, 24), (84, 25)],
+        ];
+        let coord = |&(x, y): &(usize, usize)| Coord { x, y };
+        // the line from the first bead to the last one, and back
+        for beads in cases {
+            let beads: Vec<_> = beads.iter().map(coord).collect();
+            let (start, end) = (beads[0], beads[beads.len() - 1]);
+            assert_eq!(beads, line.figure(Figure::Line, start, end));
+            let reversed: Vec<_> = beads.iter().rev().copied().collect();
+            assert_eq!(reversed, line.figure(Figure::Line, end, start));
+        }
+        // across the seam the offset lines step the other way
+        let line = pattern(Layout::Tube, Schema::new(2, 1).unwrap());
+        let beads = line.figure(Figure::Line, Coord { x: 15, y: 4 }, Coord { x: 5, y: 1 });
+        assert!(connected(&line, &beads), "{:?}", beads);
+    }
+
+    #[test]
+    fn tube_shapes_are_closed() {
+        let mut rng = rand::thread_rng();
+        for preset in &PRESETS {
+            let line = pattern(Layout::Tube, preset.schema);
+            for _ in 0..20 {
+                let mut coord = || Coord { x: rng.gen_range(0..16), y: rng.gen_range(0..10) };
+                let (start, end) = (coord(), coord());
+                for (outline, filled) in [
+                    (Figure::Rectangle { filled: false }, Figure::Rectangle { filled: true }),
+                    (Figure::Ellipse { filled: false }, Figure::Ellipse { filled: true }),
+                ] {
+                    let (outline, filled) = (line.figure(outline, start, end), line.figure(filled, start, end));
+                    assert!(connected(&line, &outline), "{} {:?} {:?} {:?}", preset.schema, start, end, outline);
+                    assert!(outline.iter().all(|coord| filled.contains(coord)));
+                    let rows = start.y.min(end.y)..=start.y.max(end.y);
+                    assert!(rows.clone().all(|row| outline.iter().any(|coord| coord.y == row)));
+                    assert!(filled.iter().all(|coord| rows.contains(&coord.y)));
+                }
+                let rectangle = line.figure(Figure::Rectangle { filled: false }, start, end);
+                assert!(rectangle.contains(&start) && rectangle.contains(&end));
+            }
+        }
+    }
+
+    #[test]
+    fn shapes_fill_the_box() {
+        let line = pattern(Layout::Loom, Schema::default());
+        let (start, end) = (Coord { x: 2, y: 1 }, Coord { x: 7, y: 8 });
+        assert_eq!(24, line.figure(Figure::Rectangle { filled: false }, start, end).len());
+        assert_eq!(48, line.figure(Figure::Rectangle { filled: true }, end, start).len());
+        let outline = line.figure(Figure::Ellipse { filled: false }, start, end);
+        let filled = line.figure(Figure::Ellipse { filled: true }, start, end);
+        assert!(outline.iter().all(|coord| filled.contains(coord)));
+        assert!(outline.len() < filled.len());
+        assert!(connected(&line, &outline) && connected(&line, &filled));
+
+        // the tube is crossed around the seam
+        let line = pattern(Layout::Tube, Schema::new(1, 0).unwrap());
+        let (start, end) = (line.coord_at(2, 1, 0), line.coord_at(4, 14, 0));
+        let rectangle = line.figure(Figure::Rectangle { filled: true }, start, end);
+        let mut positions: Vec<_> = rectangle.iter().map(|&coord| line.pos_of(coord, 0)).collect();
+        positions.sort_unstable();
+        positions.dedup();
+        assert_eq!(vec![0, 1, 14, 15], positions);
+        assert_eq!(12, rectangle.len());
+    }
+}
@@ -9,7 +9,7 @@ pub struct SimplifiedGrid<T: Debug + Clone> {
 impl <T: Debug + Clone + Default> SimplifiedGrid<T> {
     pub fn from_raw(width: NonZeroUsize, data: Vec<T>) -> Self {
         let height = NonZeroUsize::new(data.len()/width.get()).unwrap();
-        let size = Size {width, height: height};
+        let size = Size {width, height};
         Self {size, data}
     }
     pub fn size(&self) -> Size {
@@ -108,9 +108,7 @@ impl <T: Debug + Clone + Default> SimplifiedGrid<T> {
                 let mut newdata = Vec::with_capacity(newsize.capacity());
                 newdata.extend(
                     self.data.chunks(self.size.width())
-                    .map(|row| 
-                        (&row[range.clone()]).iter().map(Clone::clone)
-                    ).flatten()
+                    .flat_map(|row| row[range.clone()].iter().cloned())
                 );
                 self.size = newsize;
                 self.data = newdata;
@@ -128,13 +126,12 @@ impl <T: Debug + Clone + Default> SimplifiedGrid<T> {
     pub fn rotate(&mut self, rotation: isize) {
         let mut rotation = rotation % (self.size.width() as isize);
         if rotation < 0 {
-            rotation = rotation + self.size.width() as isize;
+            rotation += self.size.width() as isize;
         }
         let rotation = rotation as usize;
         self.data = self.data.as_slice()
             .chunks(self.size.width())
-            .map(|arr|arr.into_iter().cycle().skip(rotation).take(self.size.width()))
-            .flatten()
+            .flat_map(|arr|arr.iter().cycle().skip(rotation).take(self.size.width()))
             .map(Clone::clone)
             .collect()
     }
@@ -142,6 +139,7 @@ impl <T: Debug + Clone + Default> SimplifiedGrid<T> {
 
 
 #[test]
+#[allow(clippy::identity_op)]
 fn negative_remainder() {
     assert_eq!(-3, -3 % 10);
     assert_eq!(-3, -13 % 10);
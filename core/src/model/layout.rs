@@ -0,0 +1,117 @@
+use std::fmt;
+use serde::{Serialize, Deserialize};
+use super::Schema;
+
+/// Beadwork technique: how the rows of the pattern are placed and read
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Layout {
+    /// tubular crochet: rows follow the helix of the schema and wrap around
+    #[default]
+    Tube,
+    /// flat loom weaving: straight rows, every row is read left to right
+    Loom,
+    /// flat brick stitch: every other row is shifted by half a bead, rows are worked back and forth
+    Brick,
+    /// flat peyote: every other column is shifted by half a bead, rows are worked back and forth
+    Peyote,
+}
+
+pub const LAYOUTS: [Layout; 4] = [Layout::Tube, Layout::Loom, Layout::Brick, Layout::Peyote];
+
+impl fmt::Display for Layout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::Tube => "tube",
+            Self::Loom => "loom",
+            Self::Brick => "brick",
+            Self::Peyote => "peyote",
+        };
+        f.write_str(name)
+    }
+}
+
+impl Layout {
+    /// Rows of the flat layouts end at the edges instead of wrapping around
+    pub fn is_flat(self) -> bool {
+        self != Self::Tube
+    }
+    /// Column of the flat layout shown at the position of the row and vice versa
+    pub fn column(self, row: usize, pos: usize, width: usize) -> usize {
+        match self {
+            Self::Brick | Self::Peyote if row % 2 == 1 => width - 1 - pos,
+            _ => pos,
+        }
+    }
+    /// Top left corner of the bead shown at the position of the row, in beads
+    pub fn place(self, schema: Schema, row: usize, pos: usize) -> [f32; 2] {
+        let (x, y) = (pos as f32, row as f32);
+        match self {
+            Self::Tube => [x + schema.calculate_offset(row) as f32 / schema.base() as f32, y],
+            Self::Loom => [x, y],
+            Self::Brick => [x + (row % 2) as f32 * 0.5, y],
+            Self::Peyote => [x, y + (pos % 2) as f32 * 0.5],
+        }
+    }
+    /// Size of the whole pattern, in beads
+    pub fn extent(self, schema: Schema, width: usize, height: usize) -> [f32; 2] {
+        let (x, y) = (width as f32, height as f32);
+        match self {
+            Self::Tube => [x + (schema.base() - 1) as f32 / schema.base() as f32, y],
+            Self::Loom => [x, y],
+            Self::Brick if height > 1 => [x + 0.5, y],
+            Self::Peyote if width > 1 => [x, y + 0.5],
+            Self::Brick | Self::Peyote => [x, y],
+        }
+    }
+    /// Row and position of the bead shown at the point given in beads
+    pub fn locate(self, schema: Schema, width: usize, height: usize, [x, y]: [f32; 2]) -> Option<(usize, usize)> {
+        if x < 0.0 || y < 0.0 {
+            return None;
+        }
+        let (row, pos) = match self {
+            Self::Peyote => {
+                let pos = x as usize;
+                let y = y - (pos % 2) as f32 * 0.5;
+                if y < 0.0 {
+                    return None;
+                }
+                (y as usize, pos)
+            },
+            _ => {
+                let row = y as usize;
+                let x = x - self.place(schema, row, 0)[0];
+                if x < 0.0 {
+                    return None;
+                }
+                (row, x as usize)
+            },
+        };
+        if row < height && pos < width { Some((row, pos)) } else { None }
+    }
+    /// Rows and positions of the beads touching the given one in a flat layout
+    pub fn flat_neighbours(self, row: usize, pos: usize, width: usize, height: usize) -> Vec<(usize, usize)> {
+        let (row, pos) = (row as isize, pos as isize);
+        let mut result = vec![(row, pos - 1), (row, pos + 1)];
+        match self {
+            Self::Tube | Self::Loom => result.extend([(row - 1, pos), (row + 1, pos)]),
+            Self::Brick => {
+                // the shifted row touches the beads at the same and the next position of the others
+                let shift = if row % 2 == 1 { 1 } else { -1 };
+                for next in [row - 1, row + 1] {
+                    result.extend([(next, pos), (next, pos + shift)]);
+                }
+            },
+            Self::Peyote => {
+                result = vec![(row - 1, pos), (row + 1, pos)];
+                let shift = if pos % 2 == 1 { 1 } else { -1 };
+                for next in [pos - 1, pos + 1] {
+                    result.extend([(row, next), (row + shift, next)]);
+                }
+            },
+        }
+        result.into_iter()
+            .filter(|&(row, pos)| row >= 0 && pos >= 0 && (row as usize) < height && (pos as usize) < width)
+            .map(|(row, pos)| (row as usize, pos as usize))
+            .collect()
+    }
+}
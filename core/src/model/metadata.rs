@@ -0,0 +1,58 @@
+use super::{Serialize, Deserialize};
+
+/// Description of the pattern kept in its file next to the beads
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Metadata {
+    pub title: String,
+    pub author: String,
+    pub notes: String,
+    /// size of the beads, like `11/0`
+    pub bead_size: String,
+    /// kind of the beads, like `seed`, `cylinder` or `bugle`
+    pub bead_type: String,
+    /// seconds since the unix epoch
+    pub created: Option<u64>,
+    /// seconds since the unix epoch
+    pub modified: Option<u64>,
+}
+
+impl Metadata {
+    /// Marks the pattern saved at the time, the first save is the creation
+    pub fn touch(&mut self, now: u64) {
+        self.created.get_or_insert(now);
+        self.modified = Some(now);
+    }
+}
+
+/// UTC date and time of the seconds since the unix epoch as `YYYY-MM-DD HH:MM`
+pub fn format_date(seconds: u64) -> String {
+    let (days, time) = (seconds / 86400, seconds % 86400);
+    // civil date of the days since 1970-01-01, years counted from March
+    let z = days + 719468;
+    let era = z / 146097;
+    let doe = z % 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{year:04}-{month:02}-{day:02} {:02}:{:02}", time / 3600, time % 3600 / 60)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dates_are_formatted() {
+        assert_eq!("1970-01-01 00:00", format_date(0));
+        assert_eq!("2000-02-29 12:30", format_date(951827400));
+        assert_eq!("2024-12-31 23:59", format_date(1735689599));
+        let mut metadata = Metadata::default();
+        metadata.touch(10);
+        metadata.touch(20);
+        assert_eq!((Some(10), Some(20)), (metadata.created, metadata.modified));
+    }
+}
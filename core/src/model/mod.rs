@@ -7,14 +7,27 @@ pub mod grid;
 mod color;
 pub mod beads;
 mod faces;
+#[allow(clippy::module_inception)]
 mod model;
 mod schema;
+mod layout;
+mod figure;
+mod selection;
+mod metadata;
+mod symbols;
+mod runs;
 
 pub use model::Model;
 pub use faces::*;
-pub use beads::{Bead, BeadsLine};
-pub use color::Color;
-pub use schema::Schema;
+pub use beads::{Bead, BeadsLine, SchemaChange};
+pub use runs::Runs;
+pub use color::{Color, to_hex, from_hex};
+pub use schema::{Schema, Preset, PRESETS, MAX_BASE};
+pub use layout::{Layout, LAYOUTS};
+pub use figure::Figure;
+pub use selection::Selection;
+pub use metadata::{Metadata, format_date};
+pub use symbols::{Symbols, ALPHABET, MAX_SYMBOL_LEN, generated_symbol};
 
 #[derive(Debug, Copy, Clone)]
 pub enum Side { Top, Left, Right, Bottom }
@@ -46,19 +59,19 @@ impl Size {
     }
 }
 
-#[derive(Debug, Copy, Clone, Default, PartialEq)]
+#[derive(Debug, Copy, Clone, Default, PartialEq, Eq, Hash)]
 pub struct Coord {
     pub x: usize,
     pub y: usize,
 }
 
 impl Sub for Coord {
-    type Output = egui::Vec2;
+    type Output = (f32, f32);
 
     fn sub(self, rhs: Self) -> Self::Output {
         let x = self.x as f32 - rhs.x as f32;
         let y = self.y as f32 - rhs.y as f32;
-        egui::vec2(x, y)
+        (x, y)
     }
 }
 
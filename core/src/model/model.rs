@@ -0,0 +1,326 @@
+use super::*;
+
+/// Undoable step. Applying an action to the model returns the action reverting it
+enum Action<T: ColorTrait> {
+    /// bead edits as (previous color, coord) in the order they were made
+    Paint(Vec<(T, Coord)>),
+    /// structural edit, the whole line before it
+    Replace(BeadsLine<T>),
+}
+
+pub struct Model<T: ColorTrait> {
+    line: BeadsLine<T>,
+    height: usize,
+    undo: Vec<Action<T>>,
+    redo: Vec<Action<T>>,
+    gesture: Option<Vec<(T, Coord)>>,
+}
+
+impl<T: ColorTrait> Default for Model<T> {
+    fn default() -> Self {
+        BeadsLine::default().into()
+    }
+}
+
+impl<T: ColorTrait> Model<T> {
+    /// All bead edits until `end_gesture` are undone as one step
+    pub fn begin_gesture(&mut self) {
+        self.end_gesture();
+        self.gesture = Some(Vec::new());
+    }
+    pub fn end_gesture(&mut self) {
+        if let Some(changes) = self.gesture.take() {
+            if !changes.is_empty() {
+                self.undo.push(Action::Paint(changes));
+            }
+        }
+    }
+    fn paint(&mut self, color: T, coords: impl IntoIterator<Item=Coord>) -> bool {
+        self.paint_each(coords.into_iter().map(|coord| (coord, color.clone())))
+    }
+    /// Sets the beads to their colors as one undoable step
+    fn paint_each(&mut self, beads: impl IntoIterator<Item=(Coord, T)>) -> bool {
+        let changes: Vec<_> = beads.into_iter().filter_map(|(coord, color)| {
+            self.line.set_value(color, coord).map(|prev|(prev.color, coord))
+        }).collect();
+        if changes.is_empty() {
+            return false;
+        }
+        self.redo.clear();
+        match &mut self.gesture {
+            Some(gesture) => gesture.extend(changes),
+            None => self.undo.push(Action::Paint(changes)),
+        }
+        true
+    }
+    fn snapshot(&mut self) {
+        self.end_gesture();
+        self.undo.push(Action::Replace(self.line.clone()));
+        self.redo.clear();
+    }
+    pub fn draw_line(&mut self, color: T, start: Coord, end: Coord) -> bool {
+        let line = self.line.figure(Figure::Line, start, end);
+        self.paint(color, line)
+    }
+    pub fn draw_figure(&mut self, color: T, figure: Figure, start: Coord, end: Coord) -> bool {
+        let beads = self.line.figure(figure, start, end);
+        self.paint(color, beads)
+    }
+    /// Colors of the selection, the selected beads are cleared to the default color
+    pub fn cut(&mut self, selection: Selection) -> grid::SimplifiedGrid<T> {
+        let grid = self.line.copy(selection);
+        self.paint(T::default(), self.line.selected(selection));
+        grid
+    }
+    pub fn paste(&mut self, grid: &grid::SimplifiedGrid<T>, row: isize, pos: isize) -> bool {
+        let beads = self.line.pasted(grid, row, pos);
+        self.paint_each(beads)
+    }
+    /// Moves the selected beads by the rows and positions, the place they leave is cleared
+    pub fn move_selection(&mut self, selection: Selection, rows: isize, positions: isize) -> bool {
+        let grid = self.line.copy(selection);
+        let cleared = self.line.selected(selection).into_iter().map(|coord| (coord, T::default()));
+        let beads = self.line.pasted(&grid, selection.row as isize + rows, selection.pos as isize + positions);
+        self.paint_each(cleared.chain(beads).collect::<Vec<_>>())
+    }
+    /// Assigns the legend symbol of the color as one step, an empty symbol brings back the generated one
+    pub fn assign_symbol(&mut self, color: T, symbol: &str) -> bool {
+        let mut symbols = self.line.symbols.clone();
+        symbols.assign(color, symbol);
+        if symbols == self.line.symbols {
+            return false;
+        }
+        self.snapshot();
+        self.line.symbols = symbols;
+        true
+    }
+    pub fn fill(&mut self, color: T, coord: Coord) -> bool {
+        let area = self.line.connected_area(coord);
+        self.paint(color, area)
+    }
+    pub fn set_value(&mut self, value: T, coord: Coord) -> bool {
+        self.paint(value, Some(coord))
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    pub fn width(&self) -> usize {
+        self.line.width
+    }
+    pub fn line(&self) -> &BeadsLine<T> {
+        &self.line
+    }
+    pub fn line_mut(&mut self) -> &mut BeadsLine<T> {
+        &mut self.line
+    }
+    /// Replaces the whole pattern keeping the history
+    pub fn replace(&mut self, line: BeadsLine<T>) {
+        self.snapshot();
+        self.height = line.calculate_height();
+        self.line = line;
+    }
+    pub fn resize(&mut self, size: Size) {
+        self.snapshot();
+        self.line.resize(size);
+        self.height = size.height.get();
+    }
+    pub fn grow_top(&mut self) {
+        self.snapshot();
+        self.line.grow_top();
+        self.height += 1;
+    }
+    pub fn grow_bottom(&mut self) {
+        self.snapshot();
+        self.line.grow_bottom();
+        self.height += 1;
+    }
+    pub fn shrink_top(&mut self) {
+        if self.height > 1 {
+            self.snapshot();
+            self.line.shrink_top();
+            self.height -= 1;
+        }
+    }
+    pub fn shrink_bottom(&mut self) {
+        if self.height > 1 {
+            self.snapshot();
+            self.line.shrink_bottom();
+            self.height -= 1;
+        }
+    }
+    pub fn grow_left(&mut self) {
+        self.snapshot();
+        self.line.grow(Side::Left);
+    }
+    pub fn grow_right(&mut self) {
+        self.snapshot();
+        self.line.grow(Side::Right);
+    }
+    pub fn shrink_left(&mut self) {
+        if self.width() > 1 {
+            self.snapshot();
+            self.line.shrink(Side::Left);
+        }
+    }
+    pub fn shrink_right(&mut self) {
+        if self.width() > 1 {
+            self.snapshot();
+            self.line.shrink(Side::Right);
+        }
+    }
+    pub fn rotate(&mut self, rotation: isize) {
+        self.snapshot();
+        self.line.rotate(rotation);
+    }
+    pub fn change_layout(&mut self, layout: Layout) {
+        self.snapshot();
+        self.line.change_layout(layout);
+    }
+    pub fn change_schema(&mut self, schema: Schema, mode: SchemaChange) {
+        self.snapshot();
+        self.line.change_schema(schema, mode);
+    }
+    pub fn undo_at(&mut self, n: usize) {
+        let target = n as isize;
+        let current = self.redo.len() as isize;
+        let delta = target - current;
+        if delta >= 0 {
+            self.undo_n(delta as usize);
+        } else {
+            self.redo_n(delta.unsigned_abs());
+        }
+    }
+    /// Count of undone steps available for redo
+    pub fn undone(&self) -> usize {
+        self.redo.len()
+    }
+    pub fn max_undo(&self) -> usize {
+        self.undo.len() + self.redo.len()
+    }
+    fn apply(&mut self, action: Action<T>) -> Action<T> {
+        match action {
+            Action::Paint(changes) => {
+                let reverted = changes.into_iter().rev().filter_map(|(value, coord)| {
+                    self.line.set_value(value, coord).map(|prev|(prev.color, coord))
+                }).collect();
+                Action::Paint(reverted)
+            },
+            Action::Replace(mut line) => {
+                std::mem::swap(&mut self.line, &mut line);
+                self.height = self.line.calculate_height();
+                Action::Replace(line)
+            },
+        }
+    }
+    fn undo_n(&mut self, n: usize) {
+        self.end_gesture();
+        for _ in 0..n {
+            if let Some(action) = self.undo.pop() {
+                let reverted = self.apply(action);
+                self.redo.push(reverted);
+            }
+        }
+    }
+    fn redo_n(&mut self, n: usize) {
+        use std::mem::swap;
+        self.end_gesture();
+        swap(&mut self.undo, &mut self.redo);
+        self.undo_n(n);
+        swap(&mut self.undo, &mut self.redo);
+    }
+    pub fn undo(&mut self) {
+        self.undo_n(1);
+    }
+    pub fn redo(&mut self) {
+        self.redo_n(1);
+    }
+}
+
+impl<T: ColorTrait> From<BeadsLine<T>> for Model<T> {
+    fn from(line: BeadsLine<T>) -> Self {
+        let height = line.calculate_height();
+        Self {line, undo: vec![], redo: vec![], height, gesture: None}
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn colors(model: &Model<u32>) -> Vec<(u32, usize)> {
+        model.line().line().iter().map(|(bead, count)|(bead.color, count)).collect()
+    }
+
+    #[test]
+    fn gesture_is_one_step() {
+        let mut model = Model::<u32>::default();
+        let initial = colors(&model);
+        model.begin_gesture();
+        model.set_value(1, Coord{x: 1, y: 1});
+        model.draw_line(2, Coord{x: 1, y: 1}, Coord{x: 5, y: 3});
+        model.set_value(3, Coord{x: 5, y: 3});
+        model.end_gesture();
+        let painted = colors(&model);
+        assert_eq!(1, model.max_undo());
+        model.undo();
+        assert_eq!(initial, colors(&model));
+        model.redo();
+        assert_eq!(painted, colors(&model));
+    }
+
+    #[test]
+    fn structural_edits_are_undone() {
+        let mut model = Model::<u32>::default();
+        model.set_value(1, Coord{x: 0, y: 0});
+        let initial = colors(&model);
+        model.shrink_left();
+        model.grow_top();
+        model.resize(Size {width: NonZeroUsize::new(10).unwrap(), height: NonZeroUsize::new(20).unwrap()});
+        model.rotate(3);
+        model.change_schema(model.line().schema.switch(), SchemaChange::Reinterpret);
+        assert_eq!(6, model.max_undo());
+        model.undo_at(4);
+        assert_eq!(99, model.width());
+        assert_eq!(100, model.height());
+        model.undo();
+        assert_eq!(initial, colors(&model));
+        assert_eq!(100, model.width());
+        model.undo_at(2);
+        assert_eq!(10, model.width());
+        assert_eq!(20, model.height());
+    }
+
+    #[test]
+    fn symbols_are_undone() {
+        let mut model = Model::<u32>::default();
+        assert!(model.assign_symbol(0, "x"));
+        assert!(!model.assign_symbol(0, "x"));
+        assert_eq!("x", model.line().legend()[&0]);
+        assert!(model.assign_symbol(0, ""));
+        assert_eq!("A", model.line().legend()[&0]);
+        model.undo();
+        assert_eq!("x", model.line().legend()[&0]);
+        assert_eq!(2, model.max_undo());
+    }
+
+    #[test]
+    fn moved_selection_is_one_step() {
+        let mut model = Model::<u32>::default();
+        let selection = Selection { row: 2, pos: 98, width: 4, height: 3 };
+        let motif = grid::SimplifiedGrid::from_raw(NonZeroUsize::new(4).unwrap(), (1..=12).collect());
+        model.paste(&motif, 2, 98);
+        let pasted = colors(&model);
+        assert!(model.move_selection(selection, 1, 2));
+        assert_eq!(2, model.max_undo());
+        let first: Vec<u32> = model.line().copy(Selection { row: 3, pos: 0, ..selection }).as_table_iter()
+            .next().unwrap().copied().collect();
+        assert_eq!(vec![1, 2, 3, 4], first);
+        let left: u32 = model.line().copy(Selection { width: 2, height: 1, ..selection }).as_table_iter().flatten().sum();
+        assert_eq!(0, left);
+        model.undo();
+        assert_eq!(pasted, colors(&model));
+        let cut = model.cut(selection);
+        assert_eq!(78, cut.as_table_iter().flatten().sum::<u32>());
+        assert_eq!(vec![(0, 10000)], colors(&model));
+    }
+}
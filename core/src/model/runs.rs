@@ -0,0 +1,320 @@
+use std::collections::BTreeMap;
+use std::iter::{once, FromIterator};
+use std::ops::Range;
+
+use serde::{Serialize, Serializer, Deserialize};
+
+use super::*;
+
+/// Marks of the positions where runs start as a Fenwick tree:
+/// count of the runs before a position and the start of the n-th run are logarithmic
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct StartIndex {
+    /// node `i` holds the marks of the positions `i - lowest_bit(i)..i`
+    tree: Vec<usize>,
+}
+
+fn lowest_bit(i: usize) -> usize {
+    i & i.wrapping_neg()
+}
+
+impl StartIndex {
+    /// Index of `len` positions with the marked ones, linear
+    fn new(len: usize, starts: impl IntoIterator<Item=usize>) -> Self {
+        let mut tree = vec![0; len];
+        for start in starts {
+            tree[start] = 1;
+        }
+        for i in 1..=len {
+            let parent = i + lowest_bit(i);
+            if parent <= len {
+                tree[parent - 1] += tree[i - 1];
+            }
+        }
+        Self { tree }
+    }
+    fn mark(&mut self, position: usize) {
+        let mut i = position + 1;
+        while i <= self.tree.len() {
+            self.tree[i - 1] += 1;
+            i += lowest_bit(i);
+        }
+    }
+    fn unmark(&mut self, position: usize) {
+        let mut i = position + 1;
+        while i <= self.tree.len() {
+            self.tree[i - 1] -= 1;
+            i += lowest_bit(i);
+        }
+    }
+    /// Count of the marks before the position
+    fn count_before(&self, position: usize) -> usize {
+        let (mut i, mut count) = (position, 0);
+        while i > 0 {
+            count += self.tree[i - 1];
+            i -= lowest_bit(i);
+        }
+        count
+    }
+    /// Appends a position
+    fn push(&mut self, marked: bool) {
+        let i = self.tree.len() + 1;
+        let below = self.count_before(i - 1) - self.count_before(i - lowest_bit(i));
+        self.tree.push(below + marked as usize);
+    }
+    /// Drops the positions from `len`, the nodes left cover only the positions before it
+    fn truncate(&mut self, len: usize) {
+        self.tree.truncate(len);
+    }
+    /// Position of the n-th mark
+    fn nth(&self, n: usize) -> Option<usize> {
+        let len = self.tree.len();
+        let (mut position, mut left) = (0, n);
+        let mut step = if len == 0 { 0 } else { 1 << (usize::BITS - 1 - len.leading_zeros()) };
+        while step > 0 {
+            if position + step <= len && self.tree[position + step - 1] <= left {
+                position += step;
+                left -= self.tree[position - 1];
+            }
+            step >>= 1;
+        }
+        if position < len { Some(position) } else { None }
+    }
+}
+
+/// Run-length encoded beads indexed by the position where every run starts.
+/// Lookups, point edits and the ordinals of the runs are logarithmic, adjacent runs always differ.
+/// Serialized as the list of `(bead, count)` pairs
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(from = "Vec<(Bead<T>, usize)>")]
+pub struct Runs<T: ColorTrait> {
+    starts: BTreeMap<usize, Bead<T>>,
+    index: StartIndex,
+    len: usize,
+}
+
+impl<T: ColorTrait> Default for Runs<T> {
+    fn default() -> Self {
+        Self { starts: BTreeMap::new(), index: StartIndex::default(), len: 0 }
+    }
+}
+
+impl<T: ColorTrait> From<Vec<(Bead<T>, usize)>> for Runs<T> {
+    fn from(runs: Vec<(Bead<T>, usize)>) -> Self {
+        runs.into_iter().collect()
+    }
+}
+
+impl<T: ColorTrait> FromIterator<(Bead<T>, usize)> for Runs<T> {
+    fn from_iter<I: IntoIterator<Item = (Bead<T>, usize)>>(iter: I) -> Self {
+        let mut runs = Self::default();
+        for (bead, count) in iter {
+            runs.push_back(bead, count);
+        }
+        runs
+    }
+}
+
+impl<T: ColorTrait + Serialize> Serialize for Runs<T> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(self.iter())
+    }
+}
+
+impl<T: ColorTrait> Runs<T> {
+    /// Count of runs
+    pub fn len(&self) -> usize {
+        self.starts.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.starts.is_empty()
+    }
+    /// Count of beads
+    pub fn beads_count(&self) -> usize {
+        self.len
+    }
+    fn end_of(&self, start: usize) -> usize {
+        self.starts.range(start + 1..).next().map_or(self.len, |(end, _)| *end)
+    }
+    /// Runs with their counts in the stringing order
+    pub fn iter(&self) -> impl Iterator<Item=(&Bead<T>, usize)> + Clone + '_ {
+        let ends = self.starts.keys().skip(1).copied().chain(once(self.len));
+        self.starts.iter().zip(ends).map(|((start, bead), end)| (bead, end - start))
+    }
+    /// Beads one by one starting from the given position
+    pub fn beads_from(&self, index: usize) -> impl Iterator<Item=&Bead<T>> + Clone + '_ {
+        let start = self.starts.range(..=index).next_back().map_or(0, |(start, _)| *start);
+        let ends = self.starts.range(start + 1..).map(|(end, _)| *end).chain(once(self.len));
+        self.starts.range(start..).zip(ends)
+            .flat_map(|((start, bead), end)| (*start..end).map(move |_| bead))
+            .skip(index - start)
+    }
+    pub fn beads(&self) -> impl Iterator<Item=&Bead<T>> + Clone + '_ {
+        self.beads_from(0)
+    }
+    /// Start position and the bead of the run containing the position
+    pub fn run_containing(&self, index: usize) -> Option<(usize, &Bead<T>)> {
+        if index >= self.len {
+            return None;
+        }
+        self.starts.range(..=index).next_back().map(|(start, bead)| (*start, bead))
+    }
+    pub fn get(&self, index: usize) -> Option<&Bead<T>> {
+        self.run_containing(index).map(|(_, bead)| bead)
+    }
+    /// Ordinal of the run containing the position
+    pub fn run_index(&self, index: usize) -> Option<usize> {
+        let (start, _) = self.run_containing(index)?;
+        Some(self.index.count_before(start))
+    }
+    /// Positions of the beads of the n-th run
+    pub fn run_range(&self, run: usize) -> Option<Range<usize>> {
+        let start = self.index.nth(run)?;
+        Some(start..self.end_of(start))
+    }
+    fn insert_start(&mut self, start: usize, bead: Bead<T>) {
+        if self.starts.insert(start, bead).is_none() {
+            self.index.mark(start);
+        }
+    }
+    fn remove_start(&mut self, start: usize) {
+        if self.starts.remove(&start).is_some() {
+            self.index.unmark(start);
+        }
+    }
+    /// Replaces the bead at the position splitting and merging the runs around,
+    /// returns the replaced bead if it differs
+    pub fn set(&mut self, index: usize, bead: Bead<T>) -> Option<Bead<T>> {
+        let (start, current) = self.run_containing(index)?;
+        if *current == bead {
+            return None;
+        }
+        let previous = current.clone();
+        let end = self.end_of(start);
+        if index + 1 < end {
+            self.insert_start(index + 1, previous.clone());
+        } else if self.starts.get(&end) == Some(&bead) {
+            self.remove_start(end);
+        }
+        if index > start {
+            self.insert_start(index, bead);
+        } else if self.starts.range(..start).next_back().map(|(_, prev)| prev) == Some(&bead) {
+            self.remove_start(start);
+        } else {
+            self.insert_start(start, bead);
+        }
+        Some(previous)
+    }
+    pub fn push_back(&mut self, bead: Bead<T>, count: usize) {
+        if count == 0 {
+            return;
+        }
+        let new_run = self.starts.values().next_back() != Some(&bead);
+        if new_run {
+            self.starts.insert(self.len, bead);
+        }
+        self.index.push(new_run);
+        for _ in 1..count {
+            self.index.push(false);
+        }
+        self.len += count;
+    }
+    /// Rebuilds the index, linear
+    pub fn push_front(&mut self, bead: Bead<T>, count: usize) {
+        *self = once((bead, count)).chain(self.iter().map(|(bead, count)| (bead.clone(), count))).collect();
+    }
+    pub fn truncate_back(&mut self, count: usize) {
+        self.len = self.len.saturating_sub(count);
+        self.starts.split_off(&self.len);
+        self.index.truncate(self.len);
+    }
+    /// Rebuilds the index, linear
+    pub fn truncate_front(&mut self, count: usize) {
+        let count = count.min(self.len);
+        let rest = self.starts.split_off(&count);
+        let first = self.get(count).cloned();
+        let len = self.len - count;
+        self.starts = rest.into_iter().map(|(start, bead)| (start - count, bead)).collect();
+        if let Some(first) = first {
+            self.starts.entry(0).or_insert(first);
+        }
+        self.len = len;
+        self.index = StartIndex::new(len, self.starts.keys().copied());
+    }
+    pub fn map<X: ColorTrait>(&self, fun: impl Fn(&Bead<T>) -> Bead<X>) -> Runs<X> {
+        self.iter().map(|(bead, count)| (fun(bead), count)).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rand::Rng;
+    use super::*;
+
+    /// Reference edit: unpacks the runs, replaces the bead and packs them again
+    fn set_in_vec(line: &mut Vec<(Bead<u32>, usize)>, index: usize, value: Bead<u32>) {
+        let mut beads: Vec<_> = line.iter().flat_map(|(bead, count)| (0..*count).map(move |_| bead.clone())).collect();
+        beads[index] = value;
+        *line = crate::wrapper::Compressable::compress(beads.into_iter()).collect();
+    }
+
+    fn random_line(beads: usize, colors: u32) -> Vec<(Bead<u32>, usize)> {
+        let mut rng = rand::thread_rng();
+        let mut line = Vec::new();
+        let mut total = 0;
+        while total < beads {
+            let count = rng.gen_range(1..20).min(beads - total);
+            let color = match line.last() {
+                Some((Bead { color }, _)) => (color + rng.gen_range(1..colors)) % colors,
+                None => 0,
+            };
+            line.push((Bead::from(color), count));
+            total += count;
+        }
+        line
+    }
+
+    #[test]
+    fn edits_match_plain_runs() {
+        let mut rng = rand::thread_rng();
+        let mut line = random_line(300, 3);
+        let mut runs = Runs::from(line.clone());
+        for _ in 0..2000 {
+            let index = rng.gen_range(0..300);
+            let value = Bead::from(rng.gen_range(0..3u32));
+            let previous = runs.get(index).cloned();
+            let replaced = runs.set(index, value.clone());
+            assert_eq!(previous.filter(|p| *p != value), replaced);
+            set_in_vec(&mut line, index, value);
+            let actual: Vec<_> = runs.iter().map(|(bead, count)| (bead.clone(), count)).collect();
+            assert_eq!(line, actual);
+        }
+        assert_eq!(300, runs.beads().count());
+        let mut start = 0;
+        for (run, (_, count)) in runs.iter().enumerate() {
+            assert_eq!(Some(start..start + count), runs.run_range(run));
+            assert_eq!(Some(run), runs.run_index(start + count - 1));
+            start += count;
+        }
+        assert_eq!(None, runs.run_range(runs.len()));
+        assert_eq!(runs.beads().skip(123).collect::<Vec<_>>(), runs.beads_from(123).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn ends_are_edited() {
+        let mut runs = Runs::from(vec![(Bead::from(1u32), 3), (Bead::from(2), 2)]);
+        runs.push_front(Bead::from(1), 2);
+        runs.push_back(Bead::from(2), 1);
+        runs.push_back(Bead::from(3), 1);
+        let expected = vec![(Bead::from(1u32), 5), (Bead::from(2), 3), (Bead::from(3), 1)];
+        assert_eq!(Runs::from(expected), runs);
+        runs.truncate_front(6);
+        runs.truncate_back(1);
+        assert_eq!(Runs::from(vec![(Bead::from(2u32), 2)]), runs);
+        assert_eq!(Some(0), runs.run_index(1));
+        assert_eq!(None, runs.run_index(2));
+        let json = serde_json::to_string(&runs).unwrap();
+        assert_eq!(r#"[[{"color":2},2]]"#, json);
+        assert_eq!(runs, serde_json::from_str(&json).unwrap());
+    }
+}
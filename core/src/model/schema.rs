@@ -1,3 +1,5 @@
+use std::convert::TryFrom;
+use std::fmt;
 use serde::{Serialize, Deserialize};
 use super::Coord;
 
@@ -14,8 +16,8 @@ enum SchemaCompat {
     Actual{base_offset: usize, offset_step: usize},
 }
 
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
-#[serde(from = "SchemaCompat")]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "SchemaCompat")]
 pub struct Schema {
     base_offset: usize,
     offset_step: usize,
@@ -27,17 +29,75 @@ impl Default for Schema {
     }
 }
 
-impl From<SchemaCompat> for Schema {
-    fn from(value: SchemaCompat) -> Self {
+impl TryFrom<SchemaCompat> for Schema {
+    type Error = String;
+
+    fn try_from(value: SchemaCompat) -> Result<Self, Self::Error> {
         match value {
-            SchemaCompat::Old(SchemaOld::Straight) => Self {base_offset: 1, offset_step: 0},
-            SchemaCompat::Old(_) => Self {base_offset: 2, offset_step: 1},
-            SchemaCompat::Actual { base_offset, offset_step } => Self {base_offset, offset_step},
+            SchemaCompat::Old(SchemaOld::Straight) => Ok(Self {base_offset: 1, offset_step: 0}),
+            SchemaCompat::Old(_) => Ok(Self {base_offset: 2, offset_step: 1}),
+            SchemaCompat::Actual { base_offset, offset_step } => Self::new(base_offset, offset_step),
         }
     }
 }
 
+impl fmt::Display for Schema {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{{{},{}}}", self.base_offset, self.offset_step)
+    }
+}
+
+/// Largest base offset accepted by `Schema::new`
+pub const MAX_BASE: usize = 16;
+
+/// Rope named the way beaders call it: the beads around and whether the rounds are offset
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Preset {
+    pub name: &'static str,
+    pub around: usize,
+    pub schema: Schema,
+}
+
+const fn preset(name: &'static str, around: usize, base_offset: usize, offset_step: usize) -> Preset {
+    Preset { name, around, schema: Schema {base_offset, offset_step} }
+}
+
+/// Ropes to choose in the interface
+pub const PRESETS: [Preset; 7] = [
+    preset("6-around", 6, 1, 0),
+    preset("6-around offset", 6, 2, 1),
+    preset("7-around offset", 7, 2, 1),
+    preset("8-around offset", 8, 2, 1),
+    preset("8-around quarter offset", 8, 4, 1),
+    preset("9-around third offset", 9, 3, 1),
+    preset("7-around 3/7 offset", 7, 7, 3),
+];
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
 impl Schema {
+    /// Every row is shifted by `offset_step / base_offset` of a bead against the previous one
+    pub fn new(base_offset: usize, offset_step: usize) -> Result<Self, String> {
+        if base_offset == 0 || base_offset > MAX_BASE {
+            return Err(format!("base offset must be from 1 to {MAX_BASE}"));
+        }
+        if offset_step >= base_offset {
+            return Err("offset step must be less than the base offset".to_string());
+        }
+        if offset_step == 0 && base_offset > 1 {
+            return Err("rows without a step are the straight schema {1,0}".to_string());
+        }
+        let divisor = gcd(base_offset, offset_step);
+        if divisor > 1 {
+            return Err(format!("same rows as the schema {{{},{}}}", base_offset / divisor, offset_step / divisor));
+        }
+        Ok(Self {base_offset, offset_step})
+    }
+    pub fn offset_step(&self) -> usize {
+        self.offset_step
+    }
     pub fn switch(self) -> Self {
         match self {
             Self {base_offset: 1, offset_step: 0} => Self {base_offset: 4, offset_step: 1},
@@ -57,6 +117,28 @@ impl Schema {
     pub fn base(&self) -> usize {
         self.base_offset
     }
+    /// Beads touching the given one: two in the same row and one or two in each adjacent row.
+    /// Rows are wrapped around the seam.
+    pub fn neighbours(&self, Coord{x, y}: Coord, width: usize, height: usize) -> Vec<Coord> {
+        let shift = |dx: isize| (x as isize + dx).rem_euclid(width as isize) as usize;
+        let whole = (self.offset_step / self.base_offset) as isize;
+        let shifts: &[isize] = match self.offset_step % self.base_offset {
+            0 => &[0],
+            _ => &[0, 1],
+        };
+        let mut result = Vec::with_capacity(6);
+        result.push(Coord{x: shift(-1), y});
+        result.push(Coord{x: shift(1), y});
+        for dx in shifts {
+            if y + 1 < height {
+                result.push(Coord{x: shift(whole + dx), y: y + 1});
+            }
+            if y > 0 {
+                result.push(Coord{x: shift(-whole - dx), y: y - 1});
+            }
+        }
+        result
+    }
     pub fn make_line(&self, start: Coord, end: Coord, width: usize) -> Vec<Coord> {
         match self {
             Self {base_offset: 1, offset_step: 0} => make_line(start, end, width),
@@ -78,7 +160,8 @@ fn normalize_x(dist: &mut f32, width: usize) {
 
 fn make_line_offset(start: Coord, end: Coord, width: usize) -> Vec<Coord> {
     let coords = make_line(start, end, width);
-    let egui::Vec2{x,y} = end - start;
+    let (mut x, y) = end - start;
+    normalize_x(&mut x, width); //через шов линия идёт в другую сторону
     let need_correction = (x*y).is_sign_negative(); //координаты направлены разные стороны
     let Coord{x: mut px,y: mut py } = start;
     let mut result = Vec::with_capacity(coords.len()*2);
@@ -98,7 +181,7 @@ fn make_line(start: Coord, end: Coord, width: usize) -> Vec<Coord> {
     }
     let startx = (start.x + width) as f32; //добавляем ширину, чтобы не перейти через 0
     let starty = start.y as f32;
-    let egui::Vec2 {mut x, y} = end - start;
+    let (mut x, y) = end - start;
     normalize_x(&mut x, width);
     let dots = make_dots(x,y);
     dots.into_iter().map(|(x,y)| {
@@ -137,6 +220,18 @@ fn make_dots(mut x: f32, mut y: f32) -> Vec<(f32,f32)> {
 mod test {
     use super::*;
 
+    #[test]
+    fn schema_is_validated() {
+        for Preset { schema, .. } in PRESETS {
+            assert_eq!(Ok(schema), Schema::new(schema.base(), schema.offset_step()));
+        }
+        assert!(Schema::new(0, 0).is_err());
+        assert!(Schema::new(3, 3).is_err());
+        assert!(Schema::new(4, 0).is_err());
+        assert_eq!(Err("same rows as the schema {2,1}".to_string()), Schema::new(6, 3));
+        assert!(Schema::new(MAX_BASE + 1, 1).is_err());
+    }
+
     fn assert_line(a: (usize, usize), b: (usize, usize), line: Vec<(usize,usize)>, width: usize) {
         let (x,y) = a;
         let a = Coord {x, y};
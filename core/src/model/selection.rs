@@ -0,0 +1,108 @@
+use super::*;
+use grid::SimplifiedGrid;
+
+/// Rectangle of the grid the pattern is shown from at rotation 0:
+/// the first row, the first position in it and the size.
+/// Positions of the tube go on across the seam, flat layouts are cut at the edges
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Selection {
+    pub row: usize,
+    pub pos: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl<T: ColorTrait> BeadsLine<T> {
+    /// Rectangle with the beads in the opposite corners, the tube is crossed the short way around the seam
+    pub fn selection(&self, start: Coord, end: Coord) -> Selection {
+        let width = self.width;
+        let (a, b) = (self.pos_of(start, 0), self.pos_of(end, 0));
+        let (pos, columns) = if self.layout.is_flat() {
+            (a.min(b), a.max(b) - a.min(b) + 1)
+        } else {
+            let delta = (b + width - a) % width;
+            if 2 * delta > width { (b, width - delta + 1) } else { (a, delta + 1) }
+        };
+        let row = start.y.min(end.y);
+        Selection { row, pos, width: columns, height: start.y.max(end.y) - row + 1 }
+    }
+    /// Bead shown at the position of the row, if there is one: the tube wraps around, flat layouts end
+    fn grid_coord(&self, row: isize, pos: isize) -> Option<Coord> {
+        let width = self.width as isize;
+        let pos = if self.layout.is_flat() { pos } else { pos.rem_euclid(width) };
+        if row < 0 || row >= self.calculate_height() as isize || pos < 0 || pos >= width {
+            return None;
+        }
+        Some(self.coord_at(row as usize, pos as usize, 0))
+    }
+    /// Beads of the selection row by row, every bead once
+    pub fn selected(&self, selection: Selection) -> Vec<Coord> {
+        let columns = selection.width.min(self.width);
+        (0..selection.height).flat_map(|dy| (0..columns).map(move |dx| (dy, dx)))
+            .filter_map(|(dy, dx)| self.grid_coord((selection.row + dy) as isize, (selection.pos + dx) as isize))
+            .collect()
+    }
+    /// Colors of the selection, the part out of the pattern is left with the default color
+    pub fn copy(&self, selection: Selection) -> SimplifiedGrid<T> {
+        let colors: Vec<_> = self.line.beads().map(|bead| &bead.color).collect();
+        let data = (0..selection.height).flat_map(|dy| (0..selection.width).map(move |dx| (dy, dx)))
+            .map(|(dy, dx)| {
+                self.grid_coord((selection.row + dy) as isize, (selection.pos + dx) as isize)
+                    .map(|Coord { x, y }| colors[x + self.width * y].clone())
+                    .unwrap_or_default()
+            })
+            .collect();
+        SimplifiedGrid::from_raw(NonZeroUsize::new(selection.width).unwrap(), data)
+    }
+    /// Beads covered by the grid put with its top left corner at the position of the row, with their new colors
+    pub fn pasted(&self, grid: &SimplifiedGrid<T>, row: isize, pos: isize) -> Vec<(Coord, T)> {
+        grid.as_table_iter().enumerate().flat_map(|(dy, colors)| {
+            colors.enumerate().filter_map(move |(dx, color)| {
+                self.grid_coord(row + dy as isize, pos + dx as isize).map(|coord| (coord, color.clone()))
+            })
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn numbered(layout: Layout) -> BeadsLine<u32> {
+        let grid = SimplifiedGrid::from_raw(NonZeroUsize::new(8).unwrap(), (0..48).collect());
+        BeadsLine::from_layout_grid(grid, Schema::default(), layout)
+    }
+
+    #[test]
+    fn selection_wraps_around_the_tube() {
+        let line = numbered(Layout::Tube);
+        let selection = line.selection(line.coord_at(1, 7, 0), line.coord_at(2, 1, 0));
+        assert_eq!(Selection { row: 1, pos: 7, width: 3, height: 2 }, selection);
+        let grid = line.copy(selection);
+        let rows: Vec<Vec<u32>> = grid.as_table_iter().map(|row| row.copied().collect()).collect();
+        assert_eq!(vec![vec![15, 8, 9], vec![23, 16, 17]], rows);
+        assert_eq!(6, line.selected(selection).len());
+
+        let pasted = line.pasted(&grid, 4, -1);
+        let mut moved = line.clone();
+        for (coord, color) in pasted {
+            moved.set_value(color, coord);
+        }
+        assert_eq!(vec![vec![15, 8, 9], vec![23, 16, 17]], {
+            let grid = moved.copy(Selection { row: 4, pos: 7, width: 3, height: 2 });
+            grid.as_table_iter().map(|row| row.copied().collect::<Vec<_>>()).collect::<Vec<_>>()
+        });
+    }
+
+    #[test]
+    fn flat_selection_is_cut_at_the_edges() {
+        let line = numbered(Layout::Brick);
+        let selection = line.selection(line.coord_at(5, 6, 0), line.coord_at(4, 1, 0));
+        assert_eq!(Selection { row: 4, pos: 1, width: 6, height: 2 }, selection);
+        let grid = line.copy(Selection { row: 5, pos: 6, width: 4, height: 2 });
+        let rows: Vec<Vec<u32>> = grid.as_table_iter().map(|row| row.copied().collect()).collect();
+        assert_eq!(vec![vec![46, 47, 0, 0], vec![0, 0, 0, 0]], rows);
+        assert_eq!(2, line.pasted(&grid, 5, 6).len());
+        assert!(line.pasted(&grid, -2, 0).is_empty());
+    }
+}
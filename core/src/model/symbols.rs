@@ -0,0 +1,97 @@
+use indexmap::{IndexMap, IndexSet};
+use serde::{Serialize, Deserialize};
+use std::hash::Hash;
+
+/// Symbols generated for the colors without an assigned one.
+/// After the alphabet is exhausted pairs of its characters are used
+pub const ALPHABET: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+×÷±§¶¤#@%&*=";
+
+/// Maximal length of a symbol assigned by the user
+pub const MAX_SYMBOL_LEN: usize = 3;
+
+/// Legend symbols chosen by the user for some of the colors, stored with the pattern
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Symbols<T> {
+    assigned: Vec<(T, String)>,
+}
+
+impl<T> Default for Symbols<T> {
+    fn default() -> Self {
+        Self { assigned: Vec::new() }
+    }
+}
+
+/// n-th generated symbol
+pub fn generated_symbol(n: usize) -> String {
+    let chars: Vec<char> = ALPHABET.chars().collect();
+    let len = chars.len();
+    if n < len {
+        chars[n].to_string()
+    } else {
+        let n = n - len;
+        [chars[(n / len) % len], chars[n % len]].iter().collect()
+    }
+}
+
+impl<T: Clone + Eq + Hash> Symbols<T> {
+    pub fn get(&self, color: &T) -> Option<&str> {
+        self.assigned.iter().find(|(c, _)| c == color).map(|(_, s)| s.as_str())
+    }
+    /// Assigns the symbol to the color taking it from any other color.
+    /// Empty symbol returns the color to the generated ones
+    pub fn assign(&mut self, color: T, symbol: &str) {
+        let symbol: String = symbol.trim().chars().take(MAX_SYMBOL_LEN).collect();
+        self.assigned.retain(|(c, s)| *c != color && *s != symbol);
+        if !symbol.is_empty() {
+            self.assigned.push((color, symbol));
+        }
+    }
+    /// Colors with the symbols assigned to them
+    pub fn iter(&self) -> impl Iterator<Item=(&T, &str)> {
+        self.assigned.iter().map(|(c, s)| (c, s.as_str()))
+    }
+    pub fn map<X>(&self, fun: impl Fn(&T) -> X) -> Symbols<X> {
+        Symbols { assigned: self.assigned.iter().map(|(c, s)| (fun(c), s.clone())).collect() }
+    }
+    /// Symbols of the colors in their order: assigned ones are kept,
+    /// the rest get the first generated symbols not taken by the assigned ones
+    pub fn legend<'a>(&self, colors: impl IntoIterator<Item=&'a T>) -> IndexMap<T, String> where T: 'a {
+        let taken: IndexSet<&str> = self.assigned.iter().map(|(_, s)| s.as_str()).collect();
+        let mut free = (0..).map(generated_symbol).filter(|s| !taken.contains(s.as_str()));
+        colors.into_iter().map(|color| {
+            let symbol = match self.get(color) {
+                Some(symbol) => symbol.to_string(),
+                None => free.next().unwrap_or_default(),
+            };
+            (color.clone(), symbol)
+        }).collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn generated_are_unique() {
+        let symbols: IndexSet<_> = (0..5000).map(generated_symbol).collect();
+        assert_eq!(5000, symbols.len());
+        assert_eq!("U", generated_symbol(20));
+    }
+
+    #[test]
+    fn assigned_are_skipped() {
+        let mut symbols = Symbols::default();
+        symbols.assign(3u32, "A");
+        symbols.assign(4, " ★★★★ ");
+        let legend = symbols.legend(&[1, 2, 3, 4]);
+        let legend: Vec<_> = legend.values().map(String::as_str).collect();
+        assert_eq!(vec!["B", "C", "A", "★★★"], legend);
+        symbols.assign(1, "A");
+        symbols.assign(4, "");
+        assert_eq!(None, symbols.get(&3));
+        assert_eq!(None, symbols.get(&4));
+        assert_eq!(Some("A"), symbols.get(&1));
+    }
+}
@@ -0,0 +1,91 @@
+//! Palette files: GIMP `.gpl` and plain lists of hex colors, one per line
+use crate::model::{from_hex, to_hex, Rgba};
+
+const GPL_HEADER: &str = "GIMP Palette";
+
+/// Colors of a palette file under its name
+#[derive(Debug, Clone, PartialEq)]
+pub struct PaletteFile<T> {
+    pub name: String,
+    pub colors: Vec<T>,
+}
+
+/// Reads a GIMP palette or a list of hex colors, the name is taken when the file has none
+pub fn load<T: Rgba>(s: &str, name: &str) -> Result<PaletteFile<T>, String> {
+    let mut lines = s.lines().map(str::trim).enumerate().filter(|(_, line)| !line.is_empty()).peekable();
+    let gpl = lines.peek().is_some_and(|(_, line)| *line == GPL_HEADER);
+    let mut palette = PaletteFile { name: name.to_string(), colors: Vec::new() };
+    for (n, line) in lines {
+        let wrong = |e: String| format!("line {}: {e}", n + 1);
+        if !gpl {
+            // a name may follow the color
+            let hex = line.split_whitespace().next().unwrap_or_default();
+            palette.colors.push(from_hex(hex).map_err(|e| wrong(format!("wrong color {hex}, {e}")))?);
+            continue;
+        }
+        if line == GPL_HEADER || line.starts_with('#') || line.starts_with("Columns:") {
+            continue;
+        }
+        if let Some(name) = line.strip_prefix("Name:") {
+            palette.name = name.trim().to_string();
+            continue;
+        }
+        let rgb: Vec<_> = line.split_whitespace().take(3).map(str::parse::<u8>).collect();
+        match rgb.as_slice() {
+            &[Ok(r), Ok(g), Ok(b)] => palette.colors.push(T::from_rgba([r, g, b, 255])),
+            _ => return Err(wrong(format!("expected red, green and blue from 0 to 255: {line}"))),
+        }
+    }
+    if palette.colors.is_empty() {
+        return Err("no colors".to_string());
+    }
+    Ok(palette)
+}
+
+/// GIMP palette, the hex of every color is its name. The transparency is dropped
+pub fn to_gpl<T: Rgba>(palette: &PaletteFile<T>) -> String {
+    let mut out = format!("{GPL_HEADER}\nName: {}\n#\n", palette.name);
+    for color in &palette.colors {
+        let [r, g, b, _] = color.to_rgba();
+        out += &format!("{r:3} {g:3} {b:3}\t{}\n", to_hex(&T::from_rgba([r, g, b, 255])));
+    }
+    out
+}
+
+pub fn to_hex_list<T: Rgba>(palette: &PaletteFile<T>) -> String {
+    palette.colors.iter().map(|color| to_hex(color) + "\n").collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::Color;
+
+    const GPL: &str = "GIMP Palette
+Name: Preciosa
+Columns: 4
+# opaque colors
+255   0   0\tRed
+  0 128 255\tSky blue
+  1   2   3
+";
+
+    #[test]
+    fn gpl_is_loaded() {
+        let palette: PaletteFile<Color> = load(GPL, "file").unwrap();
+        assert_eq!("Preciosa", palette.name);
+        assert_eq!(vec![Color {r: 255, g: 0, b: 0}, Color {r: 0, g: 128, b: 255}, Color {r: 1, g: 2, b: 3}], palette.colors);
+        assert_eq!(palette, load(&to_gpl(&palette), "other").unwrap());
+        assert_eq!("line 6: expected red, green and blue from 0 to 255: 0 256 0\tSky blue", load::<Color>(&GPL.replace("0 128 255", "0 256 0"), "").unwrap_err());
+    }
+
+    #[test]
+    fn hex_list_is_loaded() {
+        let palette: PaletteFile<Color> = load("#FF0000 red\n\n#0080ff\n", "list").unwrap();
+        assert_eq!("list", palette.name);
+        assert_eq!("#FF0000\n#0080FF\n", to_hex_list(&palette));
+        assert_eq!(palette, load(&to_hex_list(&palette), "list").unwrap());
+        assert_eq!("line 2: wrong color red, expected #RRGGBB or #RRGGBBAA", load::<Color>("#FF0000\nred", "").unwrap_err());
+        assert_eq!("no colors", load::<Color>("GIMP Palette\nName: empty\n", "").unwrap_err());
+    }
+}
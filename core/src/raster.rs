@@ -0,0 +1,504 @@
+//! Conversion between bead patterns and raster images
+use std::f32::consts::PI;
+use std::num::NonZeroUsize;
+
+use crate::model::{BeadsLine, ColorTrait, Layout, Rgba, Schema};
+use crate::model::grid::SimplifiedGrid;
+
+type Pixel = [f32; 4];
+
+/// Raster image as unmultiplied srgba pixels, row by row
+#[derive(Debug, Clone)]
+pub struct Picture {
+    width: usize,
+    height: usize,
+    pixels: Vec<[u8; 4]>,
+}
+
+impl Picture {
+    pub fn new(width: usize, height: usize, background: [u8; 4]) -> Self {
+        Self {width, height, pixels: vec![background; width * height]}
+    }
+    pub fn from_raw(width: usize, height: usize, raw: &[u8]) -> Result<Self, String> {
+        if width == 0 || height == 0 {
+            return Err("empty picture".to_string());
+        }
+        if raw.len() != width * height * 4 {
+            return Err(format!("expected {} bytes of rgba, got {}", width * height * 4, raw.len()));
+        }
+        let pixels = raw.chunks_exact(4).map(|px|[px[0], px[1], px[2], px[3]]).collect();
+        Ok(Self {width, height, pixels})
+    }
+    pub fn width(&self) -> usize {
+        self.width
+    }
+    pub fn height(&self) -> usize {
+        self.height
+    }
+    /// Rows of beads keeping the proportions of the picture laid over the draft of the schema,
+    /// which is wider than `width` beads by the offsets of its rows.
+    /// `bead_aspect` is the width of a bead divided by its height
+    pub fn rows_for(&self, width: usize, bead_aspect: f32, schema: Schema) -> usize {
+        let [shown, _] = Layout::Tube.extent(schema, width, 1);
+        let rows = shown * self.height as f32 / self.width as f32 * bead_aspect;
+        (rows.round() as usize).max(1)
+    }
+    pub fn pixel(&self, x: usize, y: usize) -> [u8; 4] {
+        self.pixels[x + y * self.width]
+    }
+    /// Pixels as rgba bytes, row by row
+    pub fn to_raw(&self) -> Vec<u8> {
+        self.pixels.iter().flatten().copied().collect()
+    }
+    #[cfg(feature = "png")]
+    pub fn to_png(&self) -> Result<Vec<u8>, String> {
+        use image::ImageEncoder;
+        let mut buf = Vec::new();
+        image::codecs::png::PngEncoder::new(&mut buf).write_image(
+            &self.to_raw(),
+            self.width as u32,
+            self.height as u32,
+            image::ColorType::Rgba8,
+        ).map_err(|e|e.to_string())?;
+        Ok(buf)
+    }
+}
+
+/// Colors the imported beads are reduced to
+#[derive(Debug, Clone)]
+pub enum Quantization<T> {
+    /// nearest of the given colors
+    Palette(Vec<T>),
+    /// automatic palette of at most this count of colors
+    Auto(usize),
+}
+
+#[derive(Debug, Clone)]
+pub struct ImportOptions<T> {
+    pub width: usize,
+    pub height: usize,
+    pub schema: Schema,
+    pub quantization: Quantization<T>,
+    /// Floyd–Steinberg error diffusion over the beads lattice
+    pub dithering: bool,
+}
+
+/// Resamples the picture to beads of the given lattice and quantizes their colors
+pub fn import<T: ColorTrait + Rgba>(picture: &Picture, options: ImportOptions<T>) -> BeadsLine<T> {
+    let ImportOptions { width, height, schema, quantization, dithering } = options;
+    let width = width.max(1);
+    let height = height.max(1);
+    let mut pixels = resample(picture, width, height, &schema);
+    let palette: Vec<(T, Pixel)> = match quantization {
+        Quantization::Palette(colors) if !colors.is_empty() => colors.into_iter()
+            .map(|color|{
+                let pixel = to_pixel(color.to_rgba());
+                (color, pixel)
+            }).collect(),
+        Quantization::Palette(_) => median_cut(&pixels, 1),
+        Quantization::Auto(count) => median_cut(&pixels, count),
+    };
+    let mut data = Vec::with_capacity(width * height);
+    for row in 0..height {
+        let below = if dithering && row + 1 < height {
+            below_weights(&schema, row)
+        } else {
+            Vec::new()
+        };
+        for pos in 0..width {
+            let pixel = pixels[pos + row * width];
+            let (color, quantized) = &palette[nearest(&palette, &pixel)];
+            data.push(color.clone());
+            if !dithering {
+                continue;
+            }
+            let error = sub(pixel, *quantized);
+            if pos + 1 < width {
+                add_scaled(&mut pixels[pos + 1 + row * width], error, 7.0 / 16.0);
+            }
+            for &(shift, weight) in &below {
+                let x = (pos as isize + shift).rem_euclid(width as isize) as usize;
+                add_scaled(&mut pixels[x + (row + 1) * width], error, weight);
+            }
+        }
+    }
+    let grid = SimplifiedGrid::from_raw(NonZeroUsize::new(width).unwrap(), data);
+    BeadsLine::from_simplified_grid(grid, schema)
+}
+
+fn to_pixel(rgba: [u8; 4]) -> Pixel {
+    rgba.map(f32::from)
+}
+
+fn sub(a: Pixel, b: Pixel) -> Pixel {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2], a[3] - b[3]]
+}
+
+fn add_scaled(target: &mut Pixel, value: Pixel, scale: f32) {
+    target.iter_mut().zip(value).for_each(|(t, v)| *t += v * scale);
+}
+
+fn distance(a: &Pixel, b: &Pixel) -> f32 {
+    sub(*a, *b).iter().map(|d| d * d).sum()
+}
+
+fn nearest<T>(palette: &[(T, Pixel)], pixel: &Pixel) -> usize {
+    palette.iter().enumerate()
+        .map(|(n, (_, color))|(n, distance(color, pixel)))
+        .fold((0, f32::MAX), |best, current| if current.1 < best.1 { current } else { best })
+        .0
+}
+
+/// Averages the picture pixels covered by every bead.
+/// Beads are taken row by row as they are shown, with the row offset of the schema.
+/// The picture is wrapped horizontally like the rope.
+fn resample(picture: &Picture, width: usize, height: usize, schema: &Schema) -> Vec<Pixel> {
+    let scale_x = picture.width as f32 / width as f32;
+    let scale_y = picture.height as f32 / height as f32;
+    let mut result = Vec::with_capacity(width * height);
+    for row in 0..height {
+        let offset = schema.calculate_offset(row) as f32 / schema.base() as f32;
+        let y0 = (row as f32 * scale_y) as usize;
+        let y1 = (((row + 1) as f32 * scale_y).ceil() as usize).clamp(y0 + 1, picture.height);
+        for pos in 0..width {
+            let x0 = ((pos as f32 + offset) * scale_x) as usize;
+            let x1 = (((pos as f32 + offset + 1.0) * scale_x).ceil() as usize).max(x0 + 1);
+            let mut sum = [0.0f32; 4];
+            let mut count = 0.0;
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    let [r, g, b, a] = to_pixel(picture.pixel(x % picture.width, y));
+                    sum[0] += r * a;
+                    sum[1] += g * a;
+                    sum[2] += b * a;
+                    sum[3] += a;
+                    count += 1.0;
+                }
+            }
+            result.push(if sum[3] > 0.0 {
+                [sum[0] / sum[3], sum[1] / sum[3], sum[2] / sum[3], sum[3] / count]
+            } else {
+                [0.0; 4]
+            });
+        }
+    }
+    result
+}
+
+/// Beads of the next row touching a bead, as position shift and share of the diffused error.
+/// The classic kernel (3/16, 5/16, 1/16) is interpolated by horizontal distance,
+/// so offset lattices spread the error to the two overlapping beads.
+fn below_weights(schema: &Schema, row: usize) -> Vec<(isize, f32)> {
+    let base = schema.base() as f32;
+    let delta = (schema.calculate_offset(row + 1) as f32 - schema.calculate_offset(row) as f32) / base;
+    let weights: Vec<_> = (-2isize..=2).filter_map(|shift| {
+        let distance = shift as f32 + delta;
+        match distance {
+            d if (-1.0..=0.0).contains(&d) => Some((shift, 5.0 + 2.0 * d)),
+            d if (0.0..=1.0).contains(&d) => Some((shift, 5.0 - 4.0 * d)),
+            _ => None,
+        }
+    }).collect();
+    let total: f32 = weights.iter().map(|(_, w)| w).sum();
+    weights.into_iter().map(|(shift, w)|(shift, w / total * 9.0 / 16.0)).collect()
+}
+
+/// Palette of at most `count` colors splitting the widest channel at the median
+fn median_cut<T: Rgba>(pixels: &[Pixel], count: usize) -> Vec<(T, Pixel)> {
+    fn widest(pixels: &[Pixel]) -> (usize, f32) {
+        (0..4).map(|channel| {
+            let (min, max) = pixels.iter().fold((f32::MAX, f32::MIN), |(min, max), px| {
+                (min.min(px[channel]), max.max(px[channel]))
+            });
+            (channel, max - min)
+        }).fold((0, f32::MIN), |best, current| if current.1 > best.1 { current } else { best })
+    }
+    let mut boxes = vec![pixels.to_vec()];
+    while boxes.len() < count {
+        let (index, channel, range) = boxes.iter().enumerate()
+            .map(|(n, pixels)| {
+                let (channel, range) = widest(pixels);
+                (n, channel, range)
+            })
+            .fold((0, 0, 0.0), |best, current| if current.2 > best.2 { current } else { best });
+        if range <= 0.0 {
+            break;
+        }
+        let mut pixels = boxes.swap_remove(index);
+        pixels.sort_by(|a, b| a[channel].total_cmp(&b[channel]));
+        let tail = pixels.split_off(pixels.len() / 2);
+        boxes.push(pixels);
+        boxes.push(tail);
+    }
+    let mut palette: Vec<[u8; 4]> = boxes.iter().map(|pixels| {
+        let mut sum = [0.0f32; 4];
+        pixels.iter().for_each(|px| add_scaled(&mut sum, *px, 1.0 / pixels.len() as f32));
+        sum.map(|c| c.round().clamp(0.0, 255.0) as u8)
+    }).collect();
+    palette.sort_unstable();
+    palette.dedup();
+    palette.into_iter().map(|rgba|(T::from_rgba(rgba), to_pixel(rgba))).collect()
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Stroke {
+    pub width: f32,
+    pub color: [u8; 4],
+}
+
+/// How the rope is shown
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum View {
+    /// unwrapped, as on the canvas
+    #[default]
+    Flat,
+    /// front half of the rope, foreshortened and shaded
+    Rope,
+}
+
+/// Drawing settings, the same as used by the canvas
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub bead_size: [f32; 2],
+    pub stroke: Stroke,
+    pub filled_stroke: Stroke,
+    pub rounding: f32,
+    pub seam_rounding: f32,
+    pub rotation: isize,
+    pub view: View,
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        Self {
+            bead_size: [10.0, 10.0],
+            stroke: Stroke { width: 0.4, color: [255, 255, 255, 255] },
+            filled_stroke: Stroke { width: 0.4, color: [0, 0, 0, 0] },
+            rounding: 0.0,
+            seam_rounding: 5.0,
+            rotation: 0,
+            view: View::Flat,
+        }
+    }
+}
+
+/// Bead rectangle in pixels
+struct Rect {
+    x: f32,
+    y: f32,
+    width: f32,
+    height: f32,
+}
+
+/// Premultiplied rgba accumulator
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<Pixel>,
+}
+
+impl Canvas {
+    fn new(width: f32, height: f32) -> Self {
+        let width = (width.ceil() as usize).max(1);
+        let height = (height.ceil() as usize).max(1);
+        Self { width, height, pixels: vec![[0.0; 4]; width * height] }
+    }
+    fn blend(&mut self, x: usize, y: usize, color: Pixel, coverage: f32) {
+        let alpha = color[3] * coverage;
+        let pixel = &mut self.pixels[x + y * self.width];
+        for c in 0..3 {
+            pixel[c] = color[c] * alpha + pixel[c] * (1.0 - alpha);
+        }
+        pixel[3] = alpha + pixel[3] * (1.0 - alpha);
+    }
+    /// Rounded rectangle with the stroke centered on its border, antialiased
+    fn rect(&mut self, rect: Rect, rounding: f32, fill: Pixel, stroke: Stroke, shade: f32) {
+        let half = [rect.width / 2.0, rect.height / 2.0];
+        let center = [rect.x + half[0], rect.y + half[1]];
+        let radius = rounding.min(half[0]).min(half[1]).max(0.0);
+        let stroke_color = shaded(to_unit(stroke.color), shade);
+        let fill = shaded(fill, shade);
+        let margin = stroke.width / 2.0 + 1.0;
+        let x0 = (rect.x - margin).floor().max(0.0) as usize;
+        let y0 = (rect.y - margin).floor().max(0.0) as usize;
+        let x1 = ((rect.x + rect.width + margin).ceil() as usize).min(self.width);
+        let y1 = ((rect.y + rect.height + margin).ceil() as usize).min(self.height);
+        for y in y0..y1 {
+            for x in x0..x1 {
+                let qx = (x as f32 + 0.5 - center[0]).abs() - half[0] + radius;
+                let qy = (y as f32 + 0.5 - center[1]).abs() - half[1] + radius;
+                let outside = (qx.max(0.0).powi(2) + qy.max(0.0).powi(2)).sqrt();
+                let distance = outside + qx.max(qy).min(0.0) - radius;
+                let coverage = (0.5 - distance).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    self.blend(x, y, fill, coverage);
+                }
+                if stroke.width > 0.0 {
+                    let coverage = (stroke.width / 2.0 + 0.5 - distance.abs()).clamp(0.0, 1.0).min(stroke.width);
+                    if coverage > 0.0 {
+                        self.blend(x, y, stroke_color, coverage);
+                    }
+                }
+            }
+        }
+    }
+    fn into_picture(self) -> Picture {
+        let pixels = self.pixels.into_iter().map(|[r, g, b, a]| {
+            if a > 0.0 {
+                [r / a, g / a, b / a, a].map(|c| (c * 255.0).round().clamp(0.0, 255.0) as u8)
+            } else {
+                [0; 4]
+            }
+        }).collect();
+        Picture { width: self.width, height: self.height, pixels }
+    }
+}
+
+fn to_unit(rgba: [u8; 4]) -> Pixel {
+    rgba.map(|c| c as f32 / 255.0)
+}
+
+fn shaded([r, g, b, a]: Pixel, shade: f32) -> Pixel {
+    [r * shade, g * shade, b * shade, a]
+}
+
+/// Paints the pattern with the geometry of the canvas: beads placed by the layout,
+/// beads of the seam rounded
+pub fn render<T: ColorTrait + Rgba>(line: &BeadsLine<T>, options: &RenderOptions) -> Picture {
+    let [bead_width, bead_height] = options.bead_size;
+    let width = line.width();
+    let height = line.calculate_height();
+    let [extent_x, extent_y] = line.extent();
+    let radius = width as f32 * bead_width / (2.0 * PI);
+    let mut canvas = match options.view {
+        View::Flat => Canvas::new(extent_x * bead_width, extent_y * bead_height),
+        View::Rope => Canvas::new(2.0 * radius + bead_width, height as f32 * bead_height),
+    };
+    for row in line.table(options.rotation, 0) {
+        for (pos, (ncol, bead)) in row.iter.enumerate() {
+            let strung = row.row * width + ncol < line.progress();
+            let stroke = if strung { options.filled_stroke } else { options.stroke };
+            let rounding = if line.is_seam(ncol) { options.seam_rounding } else { options.rounding };
+            let fill = to_unit(bead.color.to_rgba());
+            let [x, y] = line.place(row.row, pos);
+            let (x, y) = (x * bead_width, y * bead_height);
+            match options.view {
+                View::Flat => {
+                    let rect = Rect { x, y, width: bead_width, height: bead_height };
+                    canvas.rect(rect, rounding, fill, stroke, 1.0);
+                },
+                View::Rope => {
+                    let angle = 2.0 * PI * (x + bead_width / 2.0) / (width as f32 * bead_width) - PI;
+                    let facing = angle.cos();
+                    if facing <= 0.05 {
+                        continue;
+                    }
+                    let center = bead_width / 2.0 + radius * (1.0 + angle.sin());
+                    let visible = bead_width * facing;
+                    let rect = Rect { x: center - visible / 2.0, y, width: visible, height: bead_height };
+                    canvas.rect(rect, rounding, fill, stroke, 0.35 + 0.65 * facing);
+                },
+            }
+        }
+    }
+    canvas.into_picture()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::model::Color;
+
+    const BLACK: Color = Color {r: 0, g: 0, b: 0};
+    const WHITE: Color = Color {r: 255, g: 255, b: 255};
+
+    fn picture(width: usize, height: usize, f: impl Fn(usize, usize) -> [u8; 4]) -> Picture {
+        let raw: Vec<u8> = (0..height).flat_map(|y|(0..width).map(move |x|(x, y))).flat_map(|(x, y)|f(x, y)).collect();
+        Picture::from_raw(width, height, &raw).unwrap()
+    }
+
+    fn options(width: usize, height: usize, quantization: Quantization<Color>, dithering: bool) -> ImportOptions<Color> {
+        let schema = Default::default();
+        ImportOptions { width, height, schema, quantization, dithering }
+    }
+
+    #[test]
+    fn halves_keep_their_colors() {
+        let picture = picture(40, 40, |x, _| if x < 20 { [0, 0, 0, 255] } else { [250, 250, 250, 255] });
+        let line = import(&picture, options(10, 10, Quantization::Palette(vec![BLACK, WHITE]), false));
+        let grid = line.simplified_grid();
+        for row in grid.as_table_iter() {
+            let row: Vec<_> = row.collect();
+            assert!(row[1..4].iter().all(|c| **c == BLACK));
+            assert!(row[6..9].iter().all(|c| **c == WHITE));
+        }
+    }
+
+    #[test]
+    fn rows_follow_the_schema() {
+        let picture = Picture::new(10, 40, [0; 4]);
+        assert_eq!(40, picture.rows_for(10, 1.0, Schema::new(1, 0).unwrap()));
+        // the draft of {2,1} is half a bead wider
+        assert_eq!(42, picture.rows_for(10, 1.0, Schema::new(2, 1).unwrap()));
+        assert_eq!(20, picture.rows_for(10, 0.5, Schema::new(1, 0).unwrap()));
+    }
+
+    #[test]
+    fn auto_palette_is_limited() {
+        let picture = picture(64, 16, |x, y| [(x * 4) as u8, (y * 16) as u8, 128, 255]);
+        let line = import(&picture, options(16, 8, Quantization::Auto(5), false));
+        let summary = line.summary();
+        assert!(summary.len() <= 5);
+        assert!(summary.len() > 1);
+    }
+
+    #[test]
+    fn dithering_mixes_gray() {
+        let picture = picture(10, 10, |_, _| [128, 128, 128, 255]);
+        let quantization = Quantization::Palette(vec![BLACK, WHITE]);
+        let flat = import(&picture, options(20, 20, quantization.clone(), false));
+        assert_eq!(1, flat.summary().len());
+        let dithered = import(&picture, options(20, 20, quantization, true));
+        let white = dithered.summary()[&WHITE] as f32;
+        assert!((white / 400.0 - 0.5).abs() < 0.05, "white share {}", white / 400.0);
+    }
+
+    #[test]
+    fn render_flat_geometry() {
+        let picture = picture(4, 4, |x, _| if x < 2 { [0, 0, 0, 255] } else { [255, 0, 0, 255] });
+        let palette = vec![BLACK, WHITE, Color {r: 255, g: 0, b: 0}];
+        let line = import(&picture, options(6, 3, Quantization::Palette(palette), false));
+        let options = RenderOptions::default();
+        let rendered = render(&line, &options);
+        assert_eq!((65, 30), (rendered.width(), rendered.height()));
+        let grid = line.simplified_grid();
+        for (row, colors) in grid.as_table_iter().enumerate() {
+            let offset = line.schema.calculate_offset(row) as f32 * 5.0;
+            for (pos, color) in colors.enumerate() {
+                let x = (offset + pos as f32 * 10.0 + 5.0) as usize;
+                assert_eq!(color.to_rgba(), rendered.pixel(x, row * 10 + 5));
+            }
+        }
+        assert_eq!([0; 4], rendered.pixel(0, 15));
+    }
+
+    #[test]
+    fn render_rope_shows_front() {
+        let line = import(&picture(1, 1, |_, _| [255, 0, 0, 255]), options(20, 2, Quantization::Auto(1), false));
+        let stroke = Stroke { width: 0.0, color: [0; 4] };
+        let options = RenderOptions { view: View::Rope, stroke, ..Default::default() };
+        let rendered = render(&line, &options);
+        let center = rendered.pixel(rendered.width() / 2, 5);
+        assert!(center[0] > 200 && center[1] == 0);
+        let side = rendered.pixel(6, 5);
+        assert!(side[0] < center[0]);
+    }
+
+    #[test]
+    fn below_weights_follow_lattice() {
+        let weights = below_weights(&Default::default(), 0);
+        assert_eq!(2, weights.len());
+        let total: f32 = weights.iter().map(|(_, w)| w).sum();
+        assert!((total - 9.0 / 16.0).abs() < 1e-6);
+    }
+}
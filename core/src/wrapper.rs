@@ -1,5 +1,3 @@
-use egui::Color32;
-
 pub struct Wrapped<T>(T);
 pub trait Wrappable where Self: Sized{
     fn wrap(&self)->Wrapped<&Self>;
@@ -26,7 +24,7 @@ impl<I, T> Iterator for Compressed<I, T> where I: Iterator<Item = T>, T: Eq {
         let mut current = None;
         std::mem::swap(&mut current, &mut self.item);
         let mut current = current?;
-        while let Some(next) = self.iter.next() {
+        for next in self.iter.by_ref() {
             if next == current.0 {
                 current.1 += 1
             } else {
@@ -122,23 +120,6 @@ impl<'a,I,T> Chunkable for I where I: Iterator<Item = &'a T> + 'a, T: 'a {
     }
 }
 
-pub trait Invertable {
-    fn invert(&self) -> Self;
-}
-
-impl Invertable for Color32 {
-    fn invert(&self) -> Self {
-        let [r,g,b, _] = self.to_array();
-        let (r,g,b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 /255.0);
-        let light = g + (r + b) / 2.0;
-        if light > 0.6 {
-            Color32::BLACK
-        } else {
-            Color32::WHITE
-        }
-    }
-}
-
 #[cfg(test)] 
 mod test {
 
@@ -154,8 +135,8 @@ mod test {
         );
         
         let x = "aabbbbccccc";
-        let z: Vec<_> = x.as_bytes().into_iter().map(|x|*x).compress().collect();
-        let y: Vec<_> = x.as_bytes().into_iter().compress().map(|(i,c)|(*i,c)).collect();
+        let z: Vec<_> = x.as_bytes().iter().copied().compress().collect();
+        let y: Vec<_> = x.as_bytes().iter().compress().map(|(i,c)|(*i,c)).collect();
         assert_eq!(
             vec![(b'a',2), (b'b',4), (b'c',5)],
             z
@@ -165,12 +146,12 @@ mod test {
 
     #[test]
     fn test_uncompress() {
-        let x: Vec<_> = vec![(1,1), (2,2), (3,3), (4,4), (5,5)].as_slice().into_iter().uncompress().copied().collect();
+        let x: Vec<_> = vec![(1,1), (2,2), (3,3), (4,4), (5,5)].as_slice().iter().uncompress().copied().collect();
         assert_eq!(
             vec![1,2,2,3,3,3,4,4,4,4,5,5,5,5,5],
             x
         );
-        let x: Vec<_> = vec![(b'a',2), (b'b',4), (b'c',5)].as_slice().into_iter().uncompress().copied().collect();
+        let x: Vec<_> = vec![(b'a',2), (b'b',4), (b'c',5)].as_slice().iter().uncompress().copied().collect();
         assert_eq!(
             "aabbbbccccc".as_bytes(),
             x.as_slice()
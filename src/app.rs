@@ -1,39 +1,102 @@
+use std::collections::HashMap;
+
 use super::*;
+use rusty_beads_core::io::Document;
+use rusty_beads_core::jbead;
+use rusty_beads_core::raster::{self, View};
+use rusty_beads_core::chart::{self, ChartOptions};
 
 #[derive(Default)]
 pub struct BeadApp {
-    beads: Model,
+    beads: Model<Color32>,
     rotation: isize,
     draw_options: Settings,
     palette: palette::Palette,
+    tool: Tool,
     drawing: bool,
     prev_coord: Option<Coord>,
-    undo: usize,
+    /// first and last bead of the shape being dragged
+    figure: Option<(Coord, Coord)>,
     show_draw_options: bool,
-    show_summary: bool,
+    summary: summary::Summary,
+    hovered_bead: Option<Coord>,
+    hovered_run: Option<usize>,
+    scroll_to: Option<Vec2>,
+    import: import::ImportDialog,
+    schema: schema::SchemaDialog,
+    tube: tube::TubePreview,
+    selection: selection::SelectionTool,
+    metadata: Metadata,
+    show_metadata: bool,
     #[cfg(target_arch="wasm32")]
     waiting_file: bool,
+    #[cfg(target_arch="wasm32")]
+    waiting_image: bool,
+    #[cfg(target_arch="wasm32")]
+    waiting_palette: bool,
 }
 
 impl BeadApp {
-    fn update_from_line(&mut self, line: BeadsLine<Color32>) {
-        let colors = line.summary().keys().copied().collect();
+    fn update_from_document(&mut self, Document { metadata, pattern }: Document<Color32>) {
+        let colors = pattern.summary().keys().copied().collect();
         self.palette.set_colors(colors);
-        self.beads = line.into();
+        self.beads = pattern.into();
+        self.metadata = metadata;
         self.draw_options.model_loaded(&self.beads);
     }
+    /// The dates of the metadata change only when the file is written
+    fn save_file(&mut self) {
+        let mut metadata = self.metadata.clone();
+        metadata.touch(io::now());
+        let document = Document { metadata, pattern: self.beads.line().clone() };
+        match io::save_file(&document) {
+            Ok(()) => self.metadata = document.metadata,
+            Err(e) => println!("{e}"),
+        }
+    }
+    fn load_image(&mut self, bytes: &[u8]) {
+        if let Err(e) = self.import.load(bytes, self.beads.width()) {
+            println!("{e}");
+        }
+    }
+    fn load_palette(&mut self, text: &str, name: &str) {
+        if let Err(e) = self.palette.load(text, name) {
+            println!("{e}");
+        }
+    }
+    fn show_import(&mut self, ctx: &egui::Context) {
+        let schema = self.beads.line().schema;
+        if let Some(mut line) = self.import.show(ctx, &self.palette, schema, self.draw_options.size) {
+            line.change_layout(self.beads.line().layout);
+            self.palette.add_colors(line.summary().keys().copied());
+            self.beads.replace(line);
+            self.draw_options.model_loaded(&self.beads);
+        }
+    }
 }
 
 #[cfg(not(target_arch="wasm32"))]
 impl BeadApp {
     fn open_file(&mut self) {
         match io::open_file() {
-            Ok(line) => {
-                self.update_from_line(line);
+            Ok(document) => {
+                self.update_from_document(document);
             },
             Err(e) => println!("{e}"),
         }
     }
+    fn open_image(&mut self) {
+        match io::open_image() {
+            Ok(bytes) => self.load_image(&bytes),
+            Err(e) => println!("{e}"),
+        }
+    }
+    fn open_palette(&mut self) {
+        match io::open_palette() {
+            Ok((text, name)) => self.load_palette(&text, &name),
+            Err(e) => println!("{e}"),
+        }
+    }
     fn on_update(&mut self) {
 
     }
@@ -44,12 +107,33 @@ impl BeadApp {
         self.waiting_file = true;
         io::open_file();
     }
+    fn open_image(&mut self) {
+        self.waiting_image = true;
+        io::open_image();
+    }
+    fn open_palette(&mut self) {
+        self.waiting_palette = true;
+        io::open_palette();
+    }
     fn on_update(&mut self) {
+        if self.waiting_palette {
+            if let Some((text, name)) = io::invoke_palette() {
+                self.waiting_palette = false;
+                self.load_palette(&text, &name);
+            }
+        }
+        if self.waiting_image {
+            if let Some(bytes) = io::invoke_image() {
+                self.waiting_image = false;
+                self.load_image(&bytes);
+            }
+        }
         if self.waiting_file {
             if let Some(beads) = io::invoke_beads() {
                 self.waiting_file = false;
-                if let Ok(line) = beads {
-                    self.update_from_line(line);
+                match beads {
+                    Ok(document) => self.update_from_document(document),
+                    Err(e) => println!("{e}"),
                 }
             } 
         }
@@ -64,143 +148,205 @@ impl eframe::App for BeadApp {
             style.text_styles.iter_mut().for_each(|(_, font)|font.size *= scale);
             ctx.set_style(style);
         }
-        self.beads.line_mut().show_summary(ctx, &mut self.show_summary);
+        self.hovered_run = self.summary.show(&mut self.beads, ctx, self.hovered_bead, self.palette.catalog().as_deref());
         self.draw_options.show(ctx, &mut self.show_draw_options, &mut self.beads);
+        self.show_import(ctx);
+        self.schema.show(ctx, &mut self.beads);
+        self.tube.show(ctx, self.beads.line(), &self.draw_options, self.rotation);
+        metadata::show_metadata(&mut self.metadata, ctx, &mut self.show_metadata);
         egui::TopBottomPanel::top("top").show(ctx, |ui|{ 
             ui.horizontal(|ui| {
                 if ui.button(rich("📂").text_style(TextStyle::Heading)).clicked() {
                     self.open_file();
                 }
                 if ui.button(rich("💾").text_style(TextStyle::Heading)).clicked() {
-                    if let Some(e) = io::save_file(self.beads.line()).err() {
-                        println!("{e}");
-                    }
+                    self.save_file();
                 }
+                if ui.button(rich("🖼").text_style(TextStyle::Heading)).clicked() {
+                    self.open_image();
+                }
+                ui.menu_button(rich("📷").text_style(TextStyle::Heading), |ui| {
+                    for (view, text) in [(View::Flat, "▭"), (View::Rope, "⛃")] {
+                        if ui.button(rich(text).text_style(TextStyle::Heading)).clicked() {
+                            ui.close_menu();
+                            let options = self.draw_options.render_options(self.rotation, view);
+                            let picture = raster::render(self.beads.line(), &options);
+                            if let Some(e) = io::save_png(&picture).err() {
+                                println!("{e}");
+                            }
+                        }
+                    }
+                });
+                ui.menu_button(rich("📄").text_style(TextStyle::Heading), |ui| {
+                    let options = ChartOptions { rotation: self.rotation, ..Default::default() };
+                    let document = if ui.button("pdf").clicked() {
+                        Some((chart::pdf(self.beads.line(), &options), "pdf"))
+                    } else if ui.button("svg").clicked() {
+                        Some((chart::svg(self.beads.line(), &options).into_bytes(), "svg"))
+                    } else if ui.button("jbb").clicked() {
+                        let document = Document { metadata: self.metadata.clone(), pattern: self.beads.line().clone() };
+                        match jbead::save(&document) {
+                            Ok(text) => Some((text.into_bytes(), "jbb")),
+                            Err(e) => {
+                                println!("{e}");
+                                None
+                            },
+                        }
+                    } else {
+                        None
+                    };
+                    if let Some((data, extension)) = document {
+                        ui.close_menu();
+                        if let Some(e) = io::save_document(&data, extension).err() {
+                            println!("{e}");
+                        }
+                    }
+                });
                 ui.toggle_value(&mut self.show_draw_options, rich("⛭").text_style(TextStyle::Heading));
-                ui.toggle_value(&mut self.show_summary, rich("🍡").text_style(TextStyle::Heading)); // //🏮 // 
+                ui.toggle_value(&mut self.summary.open, rich("🍡").text_style(TextStyle::Heading)); // //🏮 // 
+                ui.toggle_value(&mut self.schema.open, rich("🌀").text_style(TextStyle::Heading));
+                ui.toggle_value(&mut self.tube.open, rich("⛃").text_style(TextStyle::Heading));
+                ui.toggle_value(&mut self.show_metadata, rich("ℹ").text_style(TextStyle::Heading));
                 if ui.button(rich("⟲")).clicked() {
                     self.beads.undo();
                 }
-                if ui.add(Slider::new(&mut self.undo, self.beads.max_undo()..=0).show_value(false).logarithmic(true)).changed() {
-                    self.beads.undo_at(self.undo);
+                let mut undone = self.beads.undone();
+                if ui.add(Slider::new(&mut undone, self.beads.max_undo()..=0).show_value(false).logarithmic(true)).changed() {
+                    self.beads.undo_at(undone);
                 }
                 if ui.button(rich("⟳")).clicked() {
                     self.beads.redo();
                 }
+                self.tool.show(ui);
+                if self.tool == Tool::Select {
+                    self.selection.show(ui, &mut self.beads, self.hovered_bead);
+                }
+                ui.menu_button(rich("🎨").text_style(TextStyle::Heading), |ui| {
+                    if ui.button(rich("📂").text_style(TextStyle::Heading)).clicked() {
+                        ui.close_menu();
+                        self.open_palette();
+                    }
+                    for extension in ["gpl", "hex"] {
+                        if ui.button(extension).clicked() {
+                            ui.close_menu();
+                            if let Some(e) = io::save_document(self.palette.save(extension).as_bytes(), extension).err() {
+                                println!("{e}");
+                            }
+                        }
+                    }
+                    ui.checkbox(&mut self.palette.keep, "не менять палитру при открытии схемы");
+                    if let Some(catalog) = self.palette.catalog() {
+                        ui.separator();
+                        if ui.button(format!("🔄 подобрать бисер {}", catalog.name)).clicked() {
+                            ui.close_menu();
+                            let line = catalog.map_line(self.beads.line());
+                            self.palette.add_colors(line.summary().keys().copied());
+                            self.beads.replace(line);
+                        }
+                    }
+                });
                 self.palette.show(ui);
             })
         });
         egui::TopBottomPanel::bottom("bottom").show(ctx, |ui| {
-            let delta = ui.input(|i|i.scroll_delta);
-            self.rotation += (delta.x/3.0) as isize;
+            let (rotating, delta) = ui.input(|i| (i.modifiers.alt, i.scroll_delta));
+            if rotating {
+                self.rotation += ((delta.x + delta.y)/3.0) as isize;
+            }
             let w = self.beads.line().width() as isize;
             if self.rotation.abs() > w {
-                self.rotation = self.rotation % w;
+                self.rotation %= w;
             }
             ui.style_mut().spacing.slider_width = ui.available_width();
             ui.add(Slider::new(&mut self.rotation, -w..=w).show_value(false));
         });
         egui::CentralPanel::default().show(ctx, |ui| {
             let pointer = ui.input(|i| i.pointer.clone());
+            let rotating = ui.input(|i| i.modifiers.alt);
+            let space = !ctx.wants_keyboard_input() && ui.input(|i| i.key_down(Key::Space));
+            let (zoom, pinch) = ui.input(|i| (i.zoom_delta(), i.multi_touch().map(|touch| touch.start_pos)));
+            let panning = pointer.middle_down() || (space && pointer.any_down()) || pinch.is_some();
             if pointer.any_released() {
                 self.drawing = false;
                 self.prev_coord = None;
+                self.beads.end_gesture();
+                if let (Some((start, end)), Some(figure)) = (self.figure.take(), self.tool.figure()) {
+                    self.beads.draw_figure(self.palette.active_color(), figure, start, end);
+                }
+                self.selection.release(&mut self.beads);
             }
-            let drawing_color = if self.drawing {
-                Some(self.palette.active_color())
-            } else {
-                None
+            if self.tool == Tool::Select {
+                self.selection.handle_events(ui, &mut self.beads, self.hovered_bead);
+            }
+            let highlighted = self.hovered_run.and_then(|run| self.beads.line().run_range(run)).unwrap_or(0..0);
+            let (selected, preview) = match (self.figure, self.tool.figure()) {
+                (Some((start, end)), Some(figure)) => {
+                    let color = self.palette.active_color();
+                    let beads = self.beads.line().figure(figure, start, end);
+                    (Default::default(), beads.into_iter().map(|coord| (coord, color)).collect())
+                },
+                _ if self.tool == Tool::Select => {
+                    let line = self.beads.line();
+                    (self.selection.selected(line), self.selection.preview(line))
+                },
+                _ => (Default::default(), HashMap::new()),
             };
-            ui.spacing_mut().icon_spacing = 0.0;
-            ui.spacing_mut().item_spacing = vec2(0.0, 0.0);
-            let height = self.beads.height();
-            ScrollArea::vertical().enable_scrolling(!self.drawing)
-                .show_rows(ui, self.draw_options.size.y, height, |ui, range|{
-                    ui.horizontal_wrapped(|ui|{
-                        ui.spacing_mut().item_spacing = vec2(0.0, 0.0);
-                        ui.set_row_height(self.draw_options.size.y);
-                        let mut drawing = false;
-                        let box_width = self.draw_options.size.x;
-                        let offset_tail = box_width / self.beads.line().schema.base() as f32;
-                        let max_width = ui.available_width() - ui.spacing().scroll_bar_width - offset_tail;
-                        let coord = self.beads.line().table(self.rotation, range.start)
-                            .take(range.end - range.start)
-                            .fold(None, |mut coord, row| {
-                            let BeadsRow { row, offset, iter } = row;
-                            let mut usage = offset_tail * offset as f32;
-                            ui.add_space(usage);
-                            for (ncol, bead) in iter {
-                                usage += box_width;
-                                if usage > max_width {
-                                    break;
-                                }
-                                let is_seam = ncol == 0;
-                                let response = ui.add(ColorBox{options: &self.draw_options, bead, drawing_color: &drawing_color, is_seam});
-                                if response.changed() {
-                                    coord = Some(Coord{ x: ncol, y: row });
-                                }
-                                if response.hovered() && pointer.any_pressed() {
-                                    drawing = true;
-                                }
-                            }
-                            ui.add_space(offset_tail);
-                            ui.end_row();
-                            coord
-                        }); 
-                        if drawing {
-                            self.drawing = true;
-                        }
-                        if let (Some(coord), Some(color)) = (coord, drawing_color){
-                            let changed = if let Some(prev) = self.prev_coord {
-                                self.beads.draw_line(color, prev, coord)
-                            } else {
-                                self.beads.set_value(color, coord)
-                            };
-                            if changed {
-                                self.undo = 0;
-                            }
-                            self.prev_coord = Some(coord);
-                        }
-                    });
-                });
-        });
-    }
-}
-
-
-struct ColorBox<'a> {
-    options: &'a Settings,
-    bead: &'a Bead<Color32>,
-    drawing_color: &'a Option<Color32>,
-    is_seam: bool,
-}
-
-impl <'a> egui::Widget for ColorBox<'a> {
-    fn ui(self, ui: &mut Ui) -> egui::Response {
-        let (rect, mut response) = ui.allocate_at_least(self.options.size, Sense::hover());
-        let mut color = self.bead.color;
-        if let Some(drawing_color) = self.drawing_color {
-            if response.hovered() && drawing_color != &self.bead.color {
-                response.mark_changed();
-                color = *drawing_color;
+            let canvas = Canvas {
+                line: self.beads.line(),
+                options: &self.draw_options,
+                rotation: self.rotation,
+                highlighted,
+                selected,
+                preview,
+            };
+            let dragging = self.drawing || self.figure.is_some() || self.selection.dragging();
+            let mut area = ScrollArea::both()
+                .enable_scrolling(!dragging && !rotating)
+                .drag_to_scroll(panning);
+            if let Some(offset) = self.scroll_to.take() {
+                area = area.scroll_offset(offset);
             }
-        }
-        let stroke = if self.bead.filled {
-            self.options.filled_stroke
-        } else {
-            self.options.stroke
-        };
-        let rounding = if self.is_seam {
-            self.options.seam_rounding
-        } else {
-            self.options.rounding
-        };
-        ui.painter().rect(
-            rect,
-            rounding,
-            color,
-            stroke,
-        );
-        response
+            let output = area.show_viewport(ui, |ui, viewport| canvas.show(ui, viewport));
+            let hovered = output.inner;
+            self.draw_options.set_viewport(output.inner_rect.size());
+            if let Some(center) = pinch.or(pointer.hover_pos()).filter(|_| zoom != 1.0) {
+                // keep the point of the pattern under the center of zooming in place
+                let ratio = self.draw_options.zoom(zoom);
+                let center = center - output.inner_rect.min;
+                self.scroll_to = Some(((output.state.offset + center) * ratio - center).max(Vec2::ZERO));
+            }
+            self.hovered_bead = hovered;
+            match (self.tool, hovered.filter(|_| pointer.any_pressed() && !panning)) {
+                (Tool::Pencil, Some(_)) => {
+                    self.drawing = true;
+                    self.beads.begin_gesture();
+                },
+                (Tool::Fill, Some(coord)) => {
+                    self.beads.fill(self.palette.active_color(), coord);
+                },
+                (Tool::Line | Tool::Rectangle { .. } | Tool::Ellipse { .. }, Some(coord)) => {
+                    self.figure = Some((coord, coord));
+                },
+                (Tool::Select, Some(coord)) => {
+                    self.selection.press(self.beads.line(), coord);
+                },
+                _ => {},
+            }
+            if let (Some((_, end)), Some(coord)) = (&mut self.figure, hovered) {
+                *end = coord;
+            }
+            if let Some(coord) = hovered {
+                self.selection.hover(self.beads.line(), coord);
+            }
+            if let (true, Some(coord)) = (self.drawing, hovered) {
+                let color = self.palette.active_color();
+                if let Some(prev) = self.prev_coord {
+                    self.beads.draw_line(color, prev, coord);
+                } else {
+                    self.beads.set_value(color, coord);
+                }
+                self.prev_coord = Some(coord);
+            }
+        });
     }
 }
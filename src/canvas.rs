@@ -0,0 +1,88 @@
+use std::collections::{HashMap, HashSet};
+use std::ops::Range;
+
+use egui::epaint::{Mesh, RectShape, Tessellator};
+
+use super::*;
+
+/// Whole pattern as one widget: visible beads are tessellated into a single mesh,
+/// the bead under the pointer is found from the schema geometry
+pub struct Canvas<'a> {
+    pub line: &'a BeadsLine<Color32>,
+    pub options: &'a Settings,
+    pub rotation: isize,
+    /// stringing positions of the beads drawn highlighted
+    pub highlighted: Range<usize>,
+    /// beads outlined as selected
+    pub selected: HashSet<Coord>,
+    /// beads shown in the colors they get when the dragged shape or selection is dropped
+    pub preview: HashMap<Coord, Color32>,
+}
+
+impl<'a> Canvas<'a> {
+    /// Positions of the row visible in the horizontal span of the viewport
+    fn visible_positions(&self, row: usize, viewport: Rect) -> Range<usize> {
+        let size = self.options.size;
+        let shift = self.line.place(row, 0)[0] * size.x;
+        let first = ((viewport.min.x - shift) / size.x).floor().max(0.0) as usize;
+        let last = ((viewport.max.x - shift) / size.x).ceil().max(0.0) as usize;
+        first.min(self.line.width())..last.min(self.line.width())
+    }
+    /// Bead under the point given relative to the top left corner of the pattern
+    fn hit(&self, point: Vec2) -> Option<Coord> {
+        let size = self.options.size;
+        self.line.locate([point.x / size.x, point.y / size.y], self.rotation)
+    }
+    #[allow(clippy::too_many_arguments)]
+    fn bead_shape(&self, rect: Rect, bead: &Bead<Color32>, coord: Coord, ncol: usize, index: usize, progress: usize, selection: Color32) -> RectShape {
+        let options = self.options;
+        let color = self.preview.get(&coord).copied().unwrap_or(bead.color);
+        let (fill, stroke) = if index < progress {
+            (color.gamma_multiply(options.strung_opacity), options.filled_stroke)
+        } else {
+            (color, options.stroke)
+        };
+        let stroke = if self.highlighted.contains(&index) || self.selected.contains(&coord) {
+            Stroke::new(stroke.width.max(2.0), selection)
+        } else {
+            stroke
+        };
+        let rounding = if self.line.is_seam(ncol) { options.seam_rounding } else { options.rounding };
+        RectShape { rect, rounding, fill, stroke }
+    }
+    /// Paints the beads visible in the viewport of the scroll area, returns the hovered bead
+    pub fn show(self, ui: &mut Ui, viewport: Rect) -> Option<Coord> {
+        let size = self.options.size;
+        let height = self.line.calculate_height();
+        let [extent_x, extent_y] = self.line.extent();
+        let desired = vec2(extent_x * size.x, extent_y * size.y);
+        let (rect, response) = ui.allocate_exact_size(desired, Sense::hover());
+        let origin = rect.min;
+
+        // a row shifted down by half a bead is seen from the row above
+        let first = ((viewport.min.y / size.y - 0.5).floor().max(0.0) as usize).min(height);
+        let last = ((viewport.max.y / size.y).ceil().max(0.0) as usize).min(height);
+        let ctx = ui.ctx();
+        let mut tessellator = Tessellator::new(
+            ctx.pixels_per_point(),
+            ctx.tessellation_options(|options| *options),
+            ctx.fonts(|fonts| fonts.font_image_size()),
+            vec![],
+        );
+        let mut mesh = Mesh::default();
+        let selection = ui.visuals().selection.stroke.color;
+        let (width, progress) = (self.line.width(), self.line.progress());
+        for BeadsRow { row, iter, .. } in self.line.table(self.rotation, first).take(last - first) {
+            let visible = self.visible_positions(row, viewport);
+            for (pos, (ncol, bead)) in iter.enumerate().skip(visible.start).take(visible.len()) {
+                let [x, y] = self.line.place(row, pos);
+                let rect = Rect::from_min_size(origin + vec2(x * size.x, y * size.y), size);
+                let coord = self.line.coord_at(row, pos, self.rotation);
+                let shape = self.bead_shape(rect, bead, coord, ncol, row * width + ncol, progress, selection);
+                tessellator.tessellate_rect(&shape, &mut mesh);
+            }
+        }
+        ui.painter().add(Shape::mesh(mesh));
+        response.hover_pos().and_then(|pos| self.hit(pos - origin))
+    }
+}
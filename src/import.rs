@@ -0,0 +1,77 @@
+use egui::*;
+use rusty_beads_core::model::{BeadsLine, Schema};
+use rusty_beads_core::raster::{self, ImportOptions, Picture, Quantization};
+
+use crate::palette::Palette;
+
+pub struct ImportDialog {
+    picture: Option<Picture>,
+    width: usize,
+    auto_palette: bool,
+    colors: usize,
+    dithering: bool,
+}
+
+impl Default for ImportDialog {
+    fn default() -> Self {
+        Self {
+            picture: None,
+            width: 100,
+            auto_palette: false,
+            colors: 8,
+            dithering: false,
+        }
+    }
+}
+
+impl ImportDialog {
+    pub fn load(&mut self, bytes: &[u8], width: usize) -> Result<(), String> {
+        let image = image::load_from_memory(bytes).map_err(|e|e.to_string())?.to_rgba8();
+        let picture = Picture::from_raw(image.width() as usize, image.height() as usize, image.as_raw())?;
+        self.picture = Some(picture);
+        self.width = width;
+        Ok(())
+    }
+    pub fn show(&mut self, ctx: &Context, palette: &Palette, schema: Schema, bead_size: Vec2) -> Option<BeadsLine<Color32>> {
+        let Self { picture, width, auto_palette, colors, dithering } = self;
+        let image = picture.as_ref()?;
+        let height = image.rows_for(*width, bead_size.x / bead_size.y, schema);
+        let mut open = true;
+        let mut result = None;
+        Window::new("import").open(&mut open).show(ctx, |ui| {
+            ui.label(format!("{}×{}", image.width(), image.height()));
+            ui.horizontal(|ui| {
+                ui.add(DragValue::new(width).clamp_range(1..=1000));
+                ui.label("↔");
+                ui.label(format!("{height} ↕"));
+            });
+            ui.separator();
+            ui.radio_value(auto_palette, false, "🎨");
+            ui.horizontal(|ui| {
+                ui.radio_value(auto_palette, true, "🔢");
+                ui.add_enabled(*auto_palette, Slider::new(colors, 2..=32));
+            });
+            ui.checkbox(dithering, "░");
+            ui.separator();
+            if ui.button("OK").clicked() {
+                let quantization = if *auto_palette {
+                    Quantization::Auto(*colors)
+                } else {
+                    Quantization::Palette(palette.colors())
+                };
+                let options = ImportOptions {
+                    width: *width,
+                    height,
+                    schema,
+                    quantization,
+                    dithering: *dithering,
+                };
+                result = Some(raster::import(image, options));
+            }
+        });
+        if result.is_some() || !open {
+            *picture = None;
+        }
+        result
+    }
+}
@@ -1,22 +1,9 @@
-use crate::model::{Color, ColorTrait};
-use crate::model::beads::BeadsLine;
+use rusty_beads_core::io::{Document, Format};
+use rusty_beads_core::raster::Picture;
 use egui::Color32;
-use serde::Deserialize;
-
-
-fn load_from_string<'de, T: ColorTrait + Deserialize<'de>>(s: &'de str) -> Result<BeadsLine<T>, String> {
-    serde_json::from_str(s).map_err(|e|e.to_string())
-}
-
-fn load_compat(s: &str) -> Result<BeadsLine<Color32>, String> {
-    match load_from_string(&s) {
-        Ok(result) => Ok(result),
-        Err(_) => Ok(load_from_string::<Color>(&s)?.map(|c|c.clone().into())),
-    }
-}
 
 #[cfg(not(target_arch="wasm32"))]
-pub use native::{open_file, save_file};
+pub use native::{open_file, save_file, open_image, open_palette, save_png, save_document, now};
 
 #[cfg(target_arch="wasm32")]
 pub use wasm::*;
@@ -24,46 +11,61 @@ pub use wasm::*;
 #[cfg(not(target_arch="wasm32"))]
 mod native {
     use super::*;
-    use std::fs::File;
-    use std::io::{Write, Read};
-    
-    use std::path::PathBuf;
+    use rusty_beads_core::io::{load_file, save};
 
-    fn load_to_string(path: &PathBuf) -> Result<String, String> {
-        let mut file = File::open(path).map_err(|e| e.to_string())?;
-        let mut buf = String::new();
-        file.read_to_string(&mut buf).map_err(|e|e.to_string())?;
-        Ok(buf)
-    }
-
-    fn load_line(path: &PathBuf) -> Result<BeadsLine<egui::Color32>, String> {
-        let s = load_to_string(path)?;
-        load_compat(&s)
-    }
-
-    pub fn open_file() -> Result<BeadsLine<Color32>, String> {
+    pub fn open_file() -> Result<Document<Color32>, String> {
         if let Some(path) = rfd::FileDialog::new().pick_file() {
-            load_line(&path)
+            load_file(&path)
         } else {
             Err("file not picked".to_string())
         }
     }
 
-    pub fn save_file(line: &BeadsLine<Color32>) -> Result<(), String> {
+    pub fn save_file(document: &Document<Color32>) -> Result<(), String> {
         if let Some(path) = rfd::FileDialog::new().save_file() {
-            save(&path, &line)
+            save(&path, document, Format::of_path(&path))
+        } else {
+            Err("path not selected".to_string())
+        }
+    }
+
+    pub fn save_png(picture: &Picture) -> Result<(), String> {
+        save_document(&picture.to_png()?, "png")
+    }
+
+    /// Seconds since the unix epoch
+    pub fn now() -> u64 {
+        std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map_or(0, |d| d.as_secs())
+    }
+
+    pub fn save_document(data: &[u8], extension: &str) -> Result<(), String> {
+        let dialog = rfd::FileDialog::new().add_filter(extension, &[extension]);
+        if let Some(path) = dialog.save_file() {
+            std::fs::write(path, data).map_err(|e|e.to_string())
         } else {
             Err("path not selected".to_string())
         }
     }
 
-    fn save(path: &PathBuf, line: &BeadsLine<egui::Color32>) -> Result<(), String> {
-        let mut file = File::create(path)
-            .map_err(|e|e.to_string())?;
-        let serialized = serde_json::to_string(line)
-            .map_err(|e|e.to_string())?;
-        file.write_all(serialized.as_bytes())
-            .map_err(|e|e.to_string())
+    pub fn open_image() -> Result<Vec<u8>, String> {
+        let dialog = rfd::FileDialog::new().add_filter("image", &["png", "jpg", "jpeg"]);
+        if let Some(path) = dialog.pick_file() {
+            std::fs::read(path).map_err(|e|e.to_string())
+        } else {
+            Err("file not picked".to_string())
+        }
+    }
+
+    /// Text of a palette file and the name of the file
+    pub fn open_palette() -> Result<(String, String), String> {
+        let dialog = rfd::FileDialog::new().add_filter("palette", &["gpl", "hex", "txt"]);
+        if let Some(path) = dialog.pick_file() {
+            let name = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+            let text = std::fs::read_to_string(path).map_err(|e|e.to_string())?;
+            Ok((text, name))
+        } else {
+            Err("file not picked".to_string())
+        }
     }
 } 
 
@@ -72,26 +74,72 @@ mod native {
 mod wasm {
     use super::*;
     use wasm_bindgen::prelude::*;
-    use serde::Deserialize;
+    use rusty_beads_core::io::{load_document, save_to_string};
 
     #[wasm_bindgen]
     extern "C" {
         fn open_file_dialog();
         fn get_file_content() -> Option<String>;
         fn send_file(data: &str);
+        fn open_image_dialog();
+        fn get_image_content() -> Option<Vec<u8>>;
+        fn open_palette_dialog();
+        fn get_palette_content() -> Option<String>;
+        fn get_palette_name() -> String;
+        fn send_document(data: &[u8], mime: &str, name: &str);
+        #[wasm_bindgen(js_namespace = Date, js_name = now)]
+        fn date_now() -> f64;
     }
     pub fn open_file() {
         open_file_dialog();
     }
 
-    pub fn save_file(line: &BeadsLine<Color32>) -> Result<(), String> {
-        let s = serde_json::to_string(line).unwrap();
+    pub fn open_image() {
+        open_image_dialog();
+    }
+
+    pub fn invoke_image() -> Option<Vec<u8>> {
+        get_image_content()
+    }
+
+    pub fn open_palette() {
+        open_palette_dialog();
+    }
+
+    /// Text of the picked palette file and the name of the file
+    pub fn invoke_palette() -> Option<(String, String)> {
+        let text = get_palette_content()?;
+        Some((text, get_palette_name()))
+    }
+
+    pub fn save_png(picture: &Picture) -> Result<(), String> {
+        save_document(&picture.to_png()?, "png")
+    }
+
+    pub fn save_document(data: &[u8], extension: &str) -> Result<(), String> {
+        let mime = match extension {
+            "png" => "image/png",
+            "svg" => "image/svg+xml",
+            "pdf" => "application/pdf",
+            _ => "application/octet-stream",
+        };
+        send_document(data, mime, &format!("my_picture.{extension}"));
+        Ok(())
+    }
+
+    /// Seconds since the unix epoch
+    pub fn now() -> u64 {
+        (date_now() / 1000.0) as u64
+    }
+
+    pub fn save_file(document: &Document<Color32>) -> Result<(), String> {
+        let s = save_to_string(document, Format::Beads)?;
         send_file(&s);
         Ok(())
     }
 
-    pub fn invoke_beads() -> Option<Result<BeadsLine<Color32>, String>>{
+    pub fn invoke_beads() -> Option<Result<Document<Color32>, String>>{
         let text = get_file_content()?;
-        Some(load_compat(&text))
+        Some(load_document(&text))
     }
-}
\ No newline at end of file
+}
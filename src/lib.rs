@@ -1,20 +1,28 @@
-#![cfg(target_arch = "wasm32")]
+#[cfg(target_arch = "wasm32")]
 use eframe::wasm_bindgen::{self, prelude::*};
 
 use eframe::egui;
 use egui::*;
-use model::*;
+use rusty_beads_core::model::*;
 use beads::BeadsRow;
 use settings::Settings;
+use tool::Tool;
+use canvas::Canvas;
 
-mod wrapper;
-mod model;
 mod palette;
 mod settings;
 mod summary;
-mod io;
-mod app;
+mod tool;
+mod import;
+mod canvas;
+mod schema;
+mod tube;
+mod selection;
+mod metadata;
+pub mod io;
+pub mod app;
 
+#[cfg(target_arch = "wasm32")]
 #[wasm_bindgen]
 pub async fn start()  {
     let web_options = eframe::WebOptions::default();
@@ -27,4 +35,4 @@ pub async fn start()  {
 
 pub fn rich(text: &str) -> RichText {
     RichText::new(text)
-}
\ No newline at end of file
+}
@@ -1,18 +1,6 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] 
 // hide console window on Windows in release
-use eframe::{egui, CreationContext};
-use egui::*;
-use model::*;
-use beads::BeadsRow;
-use settings::Settings;
-
-mod wrapper;
-mod model;
-mod palette;
-mod settings;
-mod summary;
-mod io;
-mod app;
+use rusty_beads::app;
 
 
 #[cfg(not(target_arch="wasm32"))]
@@ -24,7 +12,3 @@ fn main() {
         Box::new(|_cc| Box::new(app::BeadApp::default())),
     ).unwrap();
 }
-
-pub fn rich(text: &str) -> RichText {
-    RichText::new(text)
-}
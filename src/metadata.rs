@@ -0,0 +1,29 @@
+use super::*;
+
+/// Shows the description of the pattern saved with it
+pub fn show_metadata(metadata: &mut Metadata, ctx: &Context, open: &mut bool) {
+    Window::new("metadata").open(open).show(ctx, |ui| {
+        Grid::new("metadata").num_columns(2).show(ui, |ui| {
+            for (label, text) in [
+                ("название", &mut metadata.title),
+                ("автор", &mut metadata.author),
+                ("размер бисера", &mut metadata.bead_size),
+                ("тип бисера", &mut metadata.bead_type),
+            ] {
+                ui.label(label);
+                ui.text_edit_singleline(text);
+                ui.end_row();
+            }
+            ui.label("заметки");
+            ui.text_edit_multiline(&mut metadata.notes);
+            ui.end_row();
+            for (label, date) in [("создан", metadata.created), ("изменён", metadata.modified)] {
+                if let Some(date) = date {
+                    ui.label(label);
+                    ui.label(format_date(date));
+                    ui.end_row();
+                }
+            }
+        });
+    });
+}
@@ -1,9 +1,27 @@
+use std::rc::Rc;
+
 use egui::*;
 use indexmap::IndexSet;
+use rusty_beads_core::catalog::{self, Catalog};
+use rusty_beads_core::palette::{self, PaletteFile};
 
-pub struct Palette {
+/// Palette the colors of an opened pattern go to
+const PATTERN: &str = "pattern";
+
+/// Colors under a name, one of them is shown at a time.
+/// The palette of a catalog takes only the beads of the catalog
+struct Named {
+    name: String,
     colors: IndexSet<Color32>,
+    catalog: Option<Rc<Catalog>>,
+}
+
+pub struct Palette {
+    palettes: Vec<Named>,
+    current: usize,
     active_color: Color32,
+    /// opening a pattern leaves the palette as it is
+    pub keep: bool,
 }
 
 impl Default for Palette {
@@ -14,38 +32,126 @@ impl Default for Palette {
             colors.insert(c);
         }
         Self { 
-            colors, 
+            palettes: vec![Named { name: "default".to_string(), colors, catalog: None }],
+            current: 0,
             active_color: C::BLUE, 
+            keep: false,
         }
     }
 }
 
 impl Palette {
+    /// Colors of an opened pattern, they go to the palette of the pattern unless the palette is kept
     pub fn set_colors(&mut self, colors: IndexSet<Color32>) {
-        self.colors = colors;
+        if !self.keep {
+            self.add_palette(PATTERN, colors);
+        }
+    }
+    /// Switches to the palette with the name, it is replaced when there is one already
+    pub fn add_palette(&mut self, name: &str, colors: IndexSet<Color32>) {
+        match self.palettes.iter().position(|palette| palette.name == name) {
+            Some(n) => {
+                self.palettes[n].colors = colors;
+                self.palettes[n].catalog = None;
+                self.current = n;
+            },
+            None => {
+                self.palettes.push(Named { name: name.to_string(), colors, catalog: None });
+                self.current = self.palettes.len() - 1;
+            },
+        }
+    }
+    /// Switches to the beads of the catalog
+    pub fn add_catalog(&mut self, catalog: Catalog) {
+        self.add_palette(&catalog.name, catalog.colors().into_iter().collect());
+        self.palettes[self.current].catalog = Some(Rc::new(catalog));
+    }
+    /// Catalog the current palette is restricted to
+    pub fn catalog(&self) -> Option<Rc<Catalog>> {
+        self.palettes[self.current].catalog.clone()
+    }
+    /// Palette or catalog file of the text, the name is used when the file has none
+    pub fn load(&mut self, text: &str, name: &str) -> Result<(), String> {
+        if catalog::is_catalog(text) {
+            self.add_catalog(catalog::load(text, name)?);
+            return Ok(());
+        }
+        let file: PaletteFile<Color32> = palette::load(text, name)?;
+        self.add_palette(&file.name, file.colors.into_iter().collect());
+        Ok(())
+    }
+    /// Current palette as a GIMP palette or a list of hex colors
+    pub fn save(&self, extension: &str) -> String {
+        let current = &self.palettes[self.current];
+        let file = PaletteFile { name: current.name.clone(), colors: current.colors.iter().copied().collect() };
+        match extension {
+            "gpl" => palette::to_gpl(&file),
+            _ => palette::to_hex_list(&file),
+        }
+    }
+    /// Adds the colors, a catalog palette takes their nearest beads
+    pub fn add_colors(&mut self, colors: impl IntoIterator<Item=Color32>) {
+        let current = &mut self.palettes[self.current];
+        match &current.catalog {
+            Some(catalog) => current.colors.extend(colors.into_iter().map(|color| catalog.nearest(&color).color::<Color32>())),
+            None => current.colors.extend(colors),
+        }
+    }
+    pub fn colors(&self) -> Vec<Color32> {
+        self.palettes[self.current].colors.iter().copied().collect()
     }
     pub fn active_color(&self) -> Color32 {
         self.active_color
     }
     pub fn show(&mut self, ui: &mut Ui) {
         ui.horizontal(|ui|{
+            ComboBox::from_id_source("palette")
+                .selected_text(&self.palettes[self.current].name)
+                .show_ui(ui, |ui| {
+                    for (n, palette) in self.palettes.iter().enumerate() {
+                        ui.selectable_value(&mut self.current, n, &palette.name);
+                    }
+                });
+            let Named { colors, catalog, .. } = &mut self.palettes[self.current];
+            let active_color = &mut self.active_color;
             if ui.button(RichText::new("🗙").color(Color32::RED)).clicked() {
-                self.colors.remove(&self.active_color);
+                colors.remove(active_color);
             }
-            ui.color_edit_button_srgba(&mut self.active_color);
+            ui.color_edit_button_srgba(active_color);
             if ui.button(RichText::new("➕").color(Color32::GREEN)).clicked() {
-                self.colors.insert(self.active_color);
-                self.active_color = self.active_color;
+                let color = catalog.as_ref().map_or(*active_color, |catalog| catalog.nearest(active_color).color());
+                colors.insert(color);
+                *active_color = color;
             }
             ui.horizontal_wrapped(|ui| {
-                for color in self.colors.clone() {
-                    ui.selectable_value(
-                        &mut self.active_color,
+                for color in colors.clone() {
+                    let swatch = ui.selectable_value(
+                        active_color,
                         color, 
                         RichText::new("⬛").color(color)
                     );
+                    if let Some(bead) = catalog.as_ref().and_then(|catalog| catalog.find(&color)) {
+                        swatch.on_hover_text(format!("{} {}", bead.code, bead.name));
+                    }
                 }
             });
         });
     }
-}
\ No newline at end of file
+}
+
+pub trait Invertable {
+    fn invert(&self) -> Self;
+}
+
+impl Invertable for Color32 {
+    fn invert(&self) -> Self {
+        let [r,g,b, _] = self.to_array();
+        let (r,g,b) = (r as f32 / 255.0, g as f32 / 255.0, b as f32 /255.0);
+        let light = g + (r + b) / 2.0;
+        if light > 0.6 {
+            Color32::BLACK
+        } else {
+            Color32::WHITE
+        }
+    }
+}
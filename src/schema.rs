@@ -0,0 +1,119 @@
+use std::num::NonZeroUsize;
+
+use egui::*;
+use rusty_beads_core::model::{grid::SimplifiedGrid, BeadsLine, Coord, Layout, Model, Preset, Schema, SchemaChange, Size, LAYOUTS, MAX_BASE, PRESETS};
+
+const PREVIEW_WIDTH: usize = 7;
+const PREVIEW_HEIGHT: usize = 6;
+const PREVIEW_BEAD: f32 = 16.0;
+
+#[derive(Default)]
+pub struct SchemaDialog {
+    pub open: bool,
+    base_offset: usize,
+    offset_step: usize,
+    /// beads around of the chosen preset, the width is changed to it with the schema
+    around: Option<usize>,
+    mode: SchemaChange,
+}
+
+impl SchemaDialog {
+    pub fn show(&mut self, ctx: &Context, beads: &mut Model<Color32>) {
+        if self.base_offset == 0 {
+            self.select(beads.line().schema);
+        }
+        let mut open = self.open;
+        Window::new("schema").open(&mut open).show(ctx, |ui| {
+            let layout = beads.line().layout;
+            ui.horizontal(|ui| {
+                for other in LAYOUTS {
+                    if ui.selectable_label(layout == other, other.to_string()).clicked() && layout != other {
+                        beads.change_layout(other);
+                    }
+                }
+            });
+            if layout != Layout::Tube {
+                return;
+            }
+            ui.separator();
+            let current = beads.line().schema;
+            let width = beads.width();
+            for Preset { name, around, schema } in PRESETS {
+                let selected = (self.base_offset, self.offset_step, self.around) == (schema.base(), schema.offset_step(), Some(around));
+                let text = if (schema, around) == (current, width) { format!("{name} {schema} ✔") } else { format!("{name} {schema}") };
+                if ui.selectable_label(selected, text).clicked() {
+                    self.select(schema);
+                    self.around = Some(around);
+                }
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                let base = ui.add(DragValue::new(&mut self.base_offset).clamp_range(1..=MAX_BASE).prefix("base "));
+                let step = ui.add(DragValue::new(&mut self.offset_step).clamp_range(0..=MAX_BASE - 1).prefix("step "));
+                if base.changed() || step.changed() {
+                    self.around = None;
+                }
+            });
+            match Schema::new(self.base_offset, self.offset_step) {
+                Ok(schema) => {
+                    preview(ui, schema);
+                    ui.radio_value(&mut self.mode, SchemaChange::Reinterpret, "move the beads");
+                    ui.radio_value(&mut self.mode, SchemaChange::KeepPicture, "keep the picture");
+                    let around = self.around.unwrap_or(width);
+                    if ui.add_enabled((schema, around) != (current, width), Button::new("OK")).clicked() {
+                        if around != width {
+                            let height = NonZeroUsize::new(beads.height()).unwrap();
+                            beads.resize(Size { width: NonZeroUsize::new(around).unwrap(), height });
+                        }
+                        if schema != current {
+                            beads.change_schema(schema, self.mode);
+                        }
+                    }
+                },
+                Err(e) => {
+                    ui.colored_label(ui.visuals().error_fg_color, e);
+                },
+            }
+        });
+        self.open = open;
+    }
+    fn select(&mut self, schema: Schema) {
+        self.base_offset = schema.base();
+        self.offset_step = schema.offset_step();
+    }
+}
+
+/// Small piece of the lattice, the first beads of the rows are joined to show the helix
+fn preview(ui: &mut Ui, schema: Schema) {
+    let data = vec![false; PREVIEW_WIDTH * PREVIEW_HEIGHT];
+    let grid = SimplifiedGrid::from_raw(NonZeroUsize::new(PREVIEW_WIDTH).unwrap(), data);
+    let mut line = BeadsLine::from_simplified_grid(grid, schema);
+    for y in 0..PREVIEW_HEIGHT {
+        line.set_value(true, Coord {x: 0, y});
+    }
+    let desired = vec2(PREVIEW_WIDTH as f32 + 1.0, PREVIEW_HEIGHT as f32) * PREVIEW_BEAD;
+    let (rect, _) = ui.allocate_exact_size(desired, Sense::hover());
+    let painter = ui.painter_at(rect);
+    let visuals = ui.visuals();
+    let tail = PREVIEW_BEAD / schema.base() as f32;
+    let mut helix = Vec::with_capacity(PREVIEW_HEIGHT);
+    for row in line.table(0, 0) {
+        let left = rect.left() + tail * row.offset as f32;
+        let top = rect.top() + row.row as f32 * PREVIEW_BEAD;
+        for (pos, (_, bead)) in row.iter.enumerate() {
+            let center = pos2(left + (pos as f32 + 0.5) * PREVIEW_BEAD, top + 0.5 * PREVIEW_BEAD);
+            let fill = if bead.color { visuals.selection.bg_fill } else { visuals.faint_bg_color };
+            painter.circle(center, PREVIEW_BEAD * 0.45, fill, visuals.widgets.noninteractive.bg_stroke);
+            if bead.color {
+                helix.push(center);
+            }
+        }
+    }
+    let stroke = visuals.selection.stroke;
+    for pair in helix.windows(2) {
+        // the helix leaving the preview at one side comes back at the other
+        if (pair[1].x - pair[0].x).abs() < PREVIEW_WIDTH as f32 * PREVIEW_BEAD / 2.0 {
+            painter.arrow(pair[0], pair[1] - pair[0], stroke);
+        }
+    }
+}
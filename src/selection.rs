@@ -0,0 +1,159 @@
+use std::collections::{HashMap, HashSet};
+
+use rusty_beads_core::io::{grid_from_string, grid_to_string};
+use rusty_beads_core::model::grid::SimplifiedGrid;
+
+use super::*;
+
+/// Dragging with the selection tool, from the bead it was pressed at to the hovered one
+#[derive(Debug, Copy, Clone)]
+enum Drag {
+    Select(Coord, Coord),
+    Move(Coord, Coord),
+}
+
+/// Rectangle of beads to copy, cut and move around, and the beads copied last.
+/// Copied beads also go to the system clipboard as json, so they can be pasted into another pattern
+#[derive(Default)]
+pub struct SelectionTool {
+    selection: Option<Selection>,
+    clipboard: Option<SimplifiedGrid<Color32>>,
+    drag: Option<Drag>,
+}
+
+impl SelectionTool {
+    pub fn show(&mut self, ui: &mut Ui, beads: &mut Model<Color32>, hovered: Option<Coord>) {
+        let selected = self.selection.is_some();
+        if ui.add_enabled(selected, Button::new(rich("✂").text_style(TextStyle::Heading))).clicked() {
+            self.cut(ui, beads);
+        }
+        if ui.add_enabled(selected, Button::new(rich("🗐").text_style(TextStyle::Heading))).clicked() {
+            self.copy(ui, beads.line());
+        }
+        let pasted = self.clipboard.is_some();
+        if ui.add_enabled(pasted, Button::new(rich("📋").text_style(TextStyle::Heading))).clicked() {
+            self.paste(beads, None, hovered);
+        }
+    }
+    /// Keyboard shortcuts of the clipboard, the pasted text is taken if it holds beads
+    pub fn handle_events(&mut self, ui: &mut Ui, beads: &mut Model<Color32>, hovered: Option<Coord>) {
+        if ui.ctx().wants_keyboard_input() {
+            return;
+        }
+        let events = ui.input(|i| i.events.clone());
+        for event in events {
+            match event {
+                Event::Copy if self.selection.is_some() => self.copy(ui, beads.line()),
+                Event::Cut if self.selection.is_some() => self.cut(ui, beads),
+                Event::Paste(text) => self.paste(beads, Some(&text), hovered),
+                Event::Key { key: Key::Escape, pressed: true, .. } => self.selection = None,
+                _ => {},
+            }
+        }
+    }
+    fn copy(&mut self, ui: &mut Ui, line: &BeadsLine<Color32>) {
+        if let Some(selection) = self.selection {
+            self.keep(ui, line.copy(selection));
+        }
+    }
+    fn cut(&mut self, ui: &mut Ui, beads: &mut Model<Color32>) {
+        if let Some(selection) = self.selection {
+            self.keep(ui, beads.cut(selection));
+        }
+    }
+    fn keep(&mut self, ui: &mut Ui, grid: SimplifiedGrid<Color32>) {
+        match grid_to_string(&grid) {
+            Ok(text) => ui.output_mut(|o| o.copied_text = text),
+            Err(e) => println!("{e}"),
+        }
+        self.clipboard = Some(grid);
+    }
+    /// Puts the beads at the hovered bead or over the selection, the pasted beads get selected
+    fn paste(&mut self, beads: &mut Model<Color32>, text: Option<&str>, hovered: Option<Coord>) {
+        let grid = match text.map(grid_from_string) {
+            Some(Ok(grid)) => grid,
+            _ => match &self.clipboard {
+                Some(grid) => grid.clone(),
+                None => return,
+            },
+        };
+        let (row, pos) = match (hovered, self.selection) {
+            (Some(coord), _) => (coord.y, beads.line().pos_of(coord, 0)),
+            (None, Some(selection)) => (selection.row, selection.pos),
+            (None, None) => (0, 0),
+        };
+        beads.paste(&grid, row as isize, pos as isize);
+        let size = grid.size();
+        self.selection = Some(Selection { row, pos, width: size.width(), height: size.height() });
+        self.clipboard = Some(grid);
+    }
+    /// Starts moving the selection when pressed inside it, a new selection otherwise
+    pub fn press(&mut self, line: &BeadsLine<Color32>, coord: Coord) {
+        let inside = self.selection.is_some_and(|selection| line.selected(selection).contains(&coord));
+        self.drag = Some(if inside { Drag::Move(coord, coord) } else { Drag::Select(coord, coord) });
+        if !inside {
+            self.selection = Some(line.selection(coord, coord));
+        }
+    }
+    pub fn hover(&mut self, line: &BeadsLine<Color32>, coord: Coord) {
+        match &mut self.drag {
+            Some(Drag::Select(start, end)) => {
+                *end = coord;
+                self.selection = Some(line.selection(*start, coord));
+            },
+            Some(Drag::Move(_, end)) => *end = coord,
+            None => {},
+        }
+    }
+    pub fn release(&mut self, beads: &mut Model<Color32>) {
+        if let (Some(Drag::Move(start, end)), Some(selection)) = (self.drag.take(), self.selection) {
+            let (rows, positions) = self.offset(beads.line(), start, end);
+            if rows != 0 || positions != 0 {
+                beads.move_selection(selection, rows, positions);
+                self.selection = Some(moved(beads.line(), selection, rows, positions));
+            }
+        }
+    }
+    pub fn dragging(&self) -> bool {
+        self.drag.is_some()
+    }
+    /// Rows and positions from one bead to another, the tube is crossed the short way around the seam
+    fn offset(&self, line: &BeadsLine<Color32>, start: Coord, end: Coord) -> (isize, isize) {
+        let width = line.width() as isize;
+        let rows = end.y as isize - start.y as isize;
+        let positions = line.pos_of(end, 0) as isize - line.pos_of(start, 0) as isize;
+        if line.layout.is_flat() || 2 * positions.abs() <= width {
+            (rows, positions)
+        } else {
+            (rows, positions - width * positions.signum())
+        }
+    }
+    /// Selected beads, outlined on the canvas
+    pub fn selected(&self, line: &BeadsLine<Color32>) -> HashSet<Coord> {
+        self.selection.map(|selection| line.selected(selection).into_iter().collect()).unwrap_or_default()
+    }
+    /// Beads as they become when the moved selection is dropped
+    pub fn preview(&self, line: &BeadsLine<Color32>) -> HashMap<Coord, Color32> {
+        match (self.drag, self.selection) {
+            (Some(Drag::Move(start, end)), Some(selection)) => {
+                let (rows, positions) = self.offset(line, start, end);
+                if rows == 0 && positions == 0 {
+                    return HashMap::new();
+                }
+                let grid = line.copy(selection);
+                let cleared = line.selected(selection).into_iter().map(|coord| (coord, Color32::default()));
+                let (row, pos) = (selection.row as isize + rows, selection.pos as isize + positions);
+                cleared.chain(line.pasted(&grid, row, pos)).collect()
+            },
+            _ => HashMap::new(),
+        }
+    }
+}
+
+/// Selection after moving, the tube wraps around
+fn moved(line: &BeadsLine<Color32>, selection: Selection, rows: isize, positions: isize) -> Selection {
+    let row = (selection.row as isize + rows).max(0) as usize;
+    let pos = selection.pos as isize + positions;
+    let pos = if line.layout.is_flat() { pos.max(0) } else { pos.rem_euclid(line.width() as isize) };
+    Selection { row, pos: pos as usize, ..selection }
+}
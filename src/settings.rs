@@ -1,12 +1,16 @@
 use egui::*;
 use crate::*;
+use rusty_beads_core::raster::{self, RenderOptions, View};
+use std::ops::RangeInclusive;
 
+const FACTOR_RANGE: RangeInclusive<f32> = 0.1..=10.0;
 
 pub struct Settings {
     pub size: Vec2,
     pub stroke: Stroke,
     pub rounding: Rounding,
     pub filled_stroke: Stroke,
+    pub strung_opacity: f32,
     pub seam_rounding: Rounding,
     factor: f32,
     origin_size: Vec2,
@@ -14,6 +18,7 @@ pub struct Settings {
     height: String,
     font_scale: f32,
     current_font_scale: f32,
+    viewport: Vec2,
 }
 
 impl Default for Settings {
@@ -22,6 +27,7 @@ impl Default for Settings {
             size: vec2(10.0, 10.0), 
             stroke: Stroke::new(0.4, Color32::WHITE), 
             filled_stroke: Stroke::new(0.4, Color32::TRANSPARENT), 
+            strung_opacity: 0.5,
             rounding: Default::default(), 
             seam_rounding: Rounding::same(5.0),
             factor: 1.0,
@@ -30,6 +36,7 @@ impl Default for Settings {
             height: 100.to_string(),
             font_scale: 1.0,
             current_font_scale: 1.0,
+            viewport: Vec2::ZERO,
         }
     }
 }
@@ -43,21 +50,59 @@ impl Settings {
         self.current_font_scale = self.font_scale;
         Some(result)
     }
-    pub fn model_loaded(&mut self, beads: &Model) {
+    /// Scales the beads keeping their proportions, returns the ratio actually applied
+    pub fn zoom(&mut self, ratio: f32) -> f32 {
+        let factor = (self.factor * ratio).clamp(*FACTOR_RANGE.start(), *FACTOR_RANGE.end());
+        let applied = factor / self.factor;
+        self.factor = factor;
+        self.size = self.origin_size * factor;
+        applied
+    }
+    /// Size of the visible part of the canvas, used to fit the pattern into it
+    pub fn set_viewport(&mut self, viewport: Vec2) {
+        self.viewport = viewport;
+    }
+    fn fit(&mut self, line: &BeadsLine<Color32>, whole: bool) {
+        let content = vec2((line.width() + 1) as f32, line.calculate_height() as f32) * self.origin_size;
+        let ratio = self.viewport / content;
+        let factor = if whole { ratio.min_elem() } else { ratio.x };
+        self.zoom(factor / self.factor);
+    }
+    pub fn render_options(&self, rotation: isize, view: View) -> RenderOptions {
+        let stroke = |stroke: Stroke| raster::Stroke { width: stroke.width, color: stroke.color.to_srgba_unmultiplied() };
+        RenderOptions {
+            bead_size: [self.size.x, self.size.y],
+            stroke: stroke(self.stroke),
+            filled_stroke: stroke(self.filled_stroke),
+            rounding: self.rounding.nw,
+            seam_rounding: self.seam_rounding.nw,
+            rotation,
+            view,
+        }
+    }
+    pub fn model_loaded(&mut self, beads: &Model<Color32>) {
         self.width = beads.width().to_string();
         self.height = beads.height().to_string();
     }
-    pub fn show(&mut self, ctx: &Context, open: &mut bool, beads: &mut Model) {
+    pub fn show(&mut self, ctx: &Context, open: &mut bool, beads: &mut Model<Color32>) {
         Window::new("settings").open(open).show(ctx, |ui| {
             self.show_ui(ui, beads);
         });
     }
-    fn show_ui(&mut self, ui: &mut Ui, beads: &mut Model) {
+    fn show_ui(&mut self, ui: &mut Ui, beads: &mut Model<Color32>) {
         ScrollArea::vertical().show(ui, |ui| {
             ui.add(Slider::new(&mut self.origin_size.x, 10.0..=100.0).text("↔"));
             ui.add(Slider::new(&mut self.origin_size.y, 10.0..=100.0).text("↕"));
-            ui.add(Slider::new(&mut self.factor, 0.1..=10.0).text("🇽").logarithmic(true));
+            ui.add(Slider::new(&mut self.factor, FACTOR_RANGE).text("🇽").logarithmic(true));
             self.size = self.origin_size * self.factor;
+            ui.horizontal(|ui| {
+                if ui.button("⬌").clicked() {
+                    self.fit(beads.line(), false);
+                }
+                if ui.button("🗖").clicked() {
+                    self.fit(beads.line(), true);
+                }
+            });
             ui.separator();
             ui.add(Slider::new(&mut self.stroke.width, 0.0..=5.0).text("☐"));
             ui.horizontal(|ui| {
@@ -70,6 +115,7 @@ impl Settings {
                 ui.color_edit_button_srgba(&mut self.filled_stroke.color);
                 ui.label(rich("⛶").color(self.filled_stroke.color));
             });
+            ui.add(Slider::new(&mut self.strung_opacity, 0.0..=1.0).text("🔅"));
             ui.separator();
             ui.horizontal(|ui| {
                 if ui.button("➕").clicked() {
@@ -90,12 +136,9 @@ impl Settings {
                 ui.label("↕");
                 ui.end_row();
                 if ui.button("OK").clicked() {
-                    match (self.width.parse(), self.height.parse()) {
-                        (Ok(width), Ok(height)) => {
-                            beads.resize(Size {width, height});
-                        }
-                        _ => {},
-                    };
+                    if let (Ok(width), Ok(height)) = (self.width.parse(), self.height.parse()) {
+                        beads.resize(Size {width, height});
+                    }
                 }
                 ui.end_row();
                 ui.vertical(|ui|ui.separator());
@@ -135,18 +178,12 @@ impl Settings {
                 ui.end_row();
                 ui.vertical(|ui|ui.separator());
                 if ui.button(rich("◀")).clicked() {
-                    beads.line_mut().rotate(-1);
+                    beads.rotate(-1);
                 };
                 ui.label(rich("💫"));
                 if ui.button(rich("▶")).clicked() {
-                    beads.line_mut().rotate(1);
+                    beads.rotate(1);
                 };
-                ui.end_row();
-                ui.vertical(|ui|ui.separator());
-                if ui.button("сменить схему").clicked() {
-                    let schema = beads.line_mut().schema.switch();
-                    beads.line_mut().change_schema(schema);
-                }
             });
         });
     }
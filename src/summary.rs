@@ -1,35 +1,95 @@
-use eframe::epaint::ahash::HashMap;
+use rusty_beads_core::catalog::Catalog;
 
-use crate::wrapper::Invertable;
+use crate::palette::Invertable;
 
 use super::*;
-const CHARS: &str = "ABCDEFGHIJKLMNOPQRSTYVWXYZ";
 
-impl BeadsLine<Color32> {
-    pub fn show_summary(&mut self, ctx: &Context, open: &mut bool ) {
-        Window::new("summary").open(open).show(ctx, |ui| {
+/// Legend and stringing list. The edited symbol is kept between frames
+/// and assigned when the field loses focus, an empty one brings back the generated symbol
+#[derive(Default)]
+pub struct Summary {
+    pub open: bool,
+    editing: Option<(Color32, String)>,
+}
+
+impl Summary {
+    /// Shows the legend and the stringing list with the run of the hovered bead highlighted,
+    /// returns the run hovered in the list. The legend names the beads of the catalog, `≈` marks the nearest ones
+    pub fn show(&mut self, beads: &mut Model<Color32>, ctx: &Context, hovered_bead: Option<Coord>, catalog: Option<&Catalog>) -> Option<usize> {
+        let line = beads.line();
+        let highlighted = hovered_bead.and_then(|coord| line.run_at(coord));
+        let mut hovered = None;
+        let mut assigned = None;
+        let mut changed = None;
+        let mut open = self.open;
+        Window::new("summary").open(&mut open).show(ctx, |ui| {
             ScrollArea::vertical().show(ui, |ui|{
                 ui.horizontal_wrapped(|ui|{
-                    let map: indexmap::IndexMap<_,_> = self.summary().into_iter().zip(CHARS.chars())
-                        .map(|((k,v), c)|(k, (v, c))).collect();
-                    for (k,(v,c)) in &map {
-                        ui.label(format!("{c}: "));
-                        ui.label(RichText::new("⬛").color(*k));
-                        ui.label(format!(" {v}"));
+                    let legend = line.legend();
+                    for (color, count) in line.summary() {
+                        let mut symbol = match &self.editing {
+                            Some((edited, text)) if *edited == color => text.clone(),
+                            _ => legend[&color].clone(),
+                        };
+                        let edit = ui.add(TextEdit::singleline(&mut symbol).desired_width(24.0));
+                        if edit.lost_focus() {
+                            self.editing = None;
+                            assigned = Some((color, symbol));
+                        } else if edit.has_focus() {
+                            self.editing = Some((color, symbol));
+                        }
+                        ui.label(RichText::new("⬛").color(color));
+                        ui.label(format!(" {count}"));
+                        if let Some(catalog) = catalog.filter(|_| color.a() > 0) {
+                            let (bead, mark) = match catalog.find(&color) {
+                                Some(bead) => (bead, ""),
+                                None => (catalog.nearest(&color), "≈"),
+                            };
+                            ui.label(format!("{mark}{}", bead.code)).on_hover_text(&bead.name);
+                        }
                         ui.end_row();
                     }
                     ui.vertical(|ui|ui.separator());
-                    let map: HashMap<_,_> = map.into_iter().map(|(k,(_,c))|(k,c)).collect();
-                    for (bead, count) in self.line_mut().into_iter() {
+                    let progress = line.progress();
+                    ui.label(format!("{progress} / {}", line.summary().values().sum::<usize>()));
+                    ui.end_row();
+                    let mut start = 0;
+                    for (n, (bead, count)) in line.line().iter().enumerate() {
                         ui.spacing_mut().item_spacing = vec2(10.0, 5.0);
-                        let text = format!("{}: {:^4}", map.get(&bead.color).copied().unwrap_or('?'), *count);
+                        let end = start + count;
+                        let mut strung = end <= progress;
+                        let text = format!("{}: {:^4}", legend.get(&bead.color).map_or("?", String::as_str), count);
                         let text_color = bead.color.invert();
-                        ui.checkbox(&mut bead.filled, 
+                        let checkbox = ui.checkbox(&mut strung, 
                             RichText::new(text).background_color(bead.color).color(text_color).monospace());
-                        ui.label("➡");
+                        if checkbox.changed() {
+                            changed = Some(if strung { end } else { start });
+                        }
+                        if checkbox.hovered() {
+                            hovered = Some(n);
+                        }
+                        match (highlighted, hovered_bead) {
+                            (Some(run), Some(coord)) if run == n => {
+                                ui.painter().rect_stroke(checkbox.rect.expand(2.0), 2.0, ui.visuals().selection.stroke);
+                                checkbox.scroll_to_me(Some(Align::Center));
+                                ui.label(RichText::new(format!("➡ {}", coord.x + line.width * coord.y + 1)).strong());
+                            },
+                            _ => {
+                                ui.label("➡");
+                            },
+                        }
+                        start = end;
                     }
                 });
             });
         });
+        self.open = open;
+        if let Some((color, symbol)) = assigned {
+            beads.assign_symbol(color, &symbol);
+        }
+        if let Some(progress) = changed {
+            beads.line_mut().set_progress(progress);
+        }
+        hovered
     }
-}
\ No newline at end of file
+}
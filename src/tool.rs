@@ -0,0 +1,39 @@
+use egui::*;
+use rusty_beads_core::model::Figure;
+use crate::rich;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum Tool {
+    #[default]
+    Pencil,
+    Fill,
+    Line,
+    Rectangle { filled: bool },
+    Ellipse { filled: bool },
+    Select,
+}
+
+impl Tool {
+    pub fn show(&mut self, ui: &mut Ui) {
+        ui.selectable_value(self, Tool::Pencil, rich("✏").text_style(TextStyle::Heading));
+        ui.selectable_value(self, Tool::Fill, rich("💧").text_style(TextStyle::Heading));
+        ui.selectable_value(self, Tool::Line, rich("╱").text_style(TextStyle::Heading));
+        // switching between the shapes keeps them filled or not
+        let filled = matches!(self, Tool::Rectangle { filled: true } | Tool::Ellipse { filled: true });
+        ui.selectable_value(self, Tool::Rectangle { filled }, rich("▭").text_style(TextStyle::Heading));
+        ui.selectable_value(self, Tool::Ellipse { filled }, rich("◯").text_style(TextStyle::Heading));
+        if let Tool::Rectangle { filled } | Tool::Ellipse { filled } = self {
+            ui.toggle_value(filled, rich("◼").text_style(TextStyle::Heading));
+        }
+        ui.selectable_value(self, Tool::Select, rich("⛶").text_style(TextStyle::Heading));
+    }
+    /// Shape drawn by dragging from one bead to another
+    pub fn figure(self) -> Option<Figure> {
+        match self {
+            Tool::Line => Some(Figure::Line),
+            Tool::Rectangle { filled } => Some(Figure::Rectangle { filled }),
+            Tool::Ellipse { filled } => Some(Figure::Ellipse { filled }),
+            Tool::Pencil | Tool::Fill | Tool::Select => None,
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use std::f32::consts::PI;
+
+use egui::epaint::{Mesh, RectShape, Tessellator};
+
+use super::*;
+
+/// Turns per second of the auto-spin
+const SPIN_SPEED: f32 = 0.1;
+
+/// Pattern rolled into a rope: beads lie on the helix of the stringing order
+/// and are shaded by how much they face the viewer
+#[derive(Default)]
+pub struct TubePreview {
+    pub open: bool,
+    spin: bool,
+    /// rotation added by dragging and spinning, in beads
+    turn: f32,
+}
+
+impl TubePreview {
+    pub fn show(&mut self, ctx: &Context, line: &BeadsLine<Color32>, options: &Settings, rotation: isize) {
+        let mut open = self.open;
+        Window::new("tube").open(&mut open).default_size(vec2(240.0, 400.0)).show(ctx, |ui| {
+            ui.toggle_value(&mut self.spin, "🔄");
+            let width = line.width() as f32;
+            if self.spin {
+                self.turn = (self.turn + ui.input(|i| i.stable_dt) * SPIN_SPEED * width) % width;
+                ctx.request_repaint();
+            }
+            ScrollArea::vertical().show_viewport(ui, |ui, viewport| {
+                self.paint(ui, viewport, line, options, rotation);
+            });
+        });
+        self.open = open;
+    }
+    fn paint(&mut self, ui: &mut Ui, viewport: Rect, line: &BeadsLine<Color32>, options: &Settings, rotation: isize) {
+        let width = line.width() as f32;
+        let height = line.calculate_height();
+        // beads keep the proportions of the canvas, the rope takes the width of the window
+        let bead_width = (ui.available_width() * PI / width).min(options.size.x);
+        let bead = vec2(bead_width, bead_width * options.size.y / options.size.x);
+        let radius = width * bead.x / (2.0 * PI);
+        let helix = !line.layout.is_flat();
+        let desired = vec2(2.0 * radius + bead.x, (height + 1) as f32 * bead.y);
+        let (rect, response) = ui.allocate_exact_size(desired, Sense::drag());
+        if response.dragged() {
+            self.turn -= response.drag_delta().x / radius * width / (2.0 * PI);
+        }
+        let axis = rect.center().x;
+        let back = ui.visuals().extreme_bg_color;
+        ui.painter().rect_filled(Rect::from_x_y_ranges(axis - radius..=axis + radius, rect.y_range()), 0.0, back);
+
+        let first = ((viewport.min.y / bead.y).floor().max(1.0) as usize - 1).min(height);
+        let last = ((viewport.max.y / bead.y).ceil().max(0.0) as usize).min(height);
+        let ctx = ui.ctx();
+        let mut tessellator = Tessellator::new(
+            ctx.pixels_per_point(),
+            ctx.tessellation_options(|options| *options),
+            ctx.fonts(|fonts| fonts.font_image_size()),
+            vec![],
+        );
+        let mut mesh = Mesh::default();
+        for BeadsRow { row, iter, .. } in line.table(rotation, first).take(last - first) {
+            for (pos, (ncol, bead_color)) in iter.enumerate() {
+                let [x, _] = line.place(row, pos);
+                let angle = 2.0 * PI * (x + 0.5 + self.turn) / width - PI;
+                let facing = angle.cos();
+                if facing <= 0.05 {
+                    continue;
+                }
+                // every bead of the rope rises by a row per turn
+                let rise = if helix { ncol as f32 / width } else { 0.0 };
+                let center = pos2(axis + radius * angle.sin(), rect.top() + (row as f32 + rise + 0.5) * bead.y);
+                let shade = 0.35 + 0.65 * facing;
+                let [r, g, b, a] = bead_color.color.to_srgba_unmultiplied();
+                let fill = Color32::from_rgba_unmultiplied(
+                    (r as f32 * shade) as u8, (g as f32 * shade) as u8, (b as f32 * shade) as u8, a,
+                );
+                let stroke = Stroke::new(options.stroke.width * facing, options.stroke.color);
+                let shape = RectShape {
+                    rect: Rect::from_center_size(center, vec2(bead.x * facing, bead.y)),
+                    rounding: Rounding::same(bead.y * 0.3),
+                    fill,
+                    stroke,
+                };
+                tessellator.tessellate_rect(&shape, &mut mesh);
+            }
+        }
+        ui.painter().add(Shape::mesh(mesh));
+    }
+}